@@ -0,0 +1,86 @@
+use super::bitboard::{EMPTY, LIGHT_SQUARES};
+use super::board::Board;
+use super::colour::Colour;
+use super::game_status::GameStatus;
+use super::piece_kind::PieceKind;
+use super::r#move::Move;
+
+impl Board {
+    // Captures are compulsory and there is no check or castling, the king moves like any other piece
+    pub fn antichess_moves(&self) -> Vec<Move> {
+        let mut moves = self.pseudo_legal_moves();
+        moves.retain(|m| self.castling(*m).is_none());
+        if moves.iter().any(|m| self.is_capture(*m)) {
+            moves.retain(|m| self.is_capture(*m));
+        }
+        moves
+    }
+
+    // Running out of pieces or of moves wins
+    pub fn antichess_status(&self) -> Option<GameStatus> {
+        if self.possible_moves().is_empty() {
+            Some(GameStatus::VariantWin)
+        } else {
+            None
+        }
+    }
+
+    // Nobody can give their pieces away when only bishops are left and the two sides' bishops stand on different square colours
+    pub fn is_antichess_insufficient_material(&self) -> bool {
+        let bishops = self.pieces(PieceKind::Bishop);
+        if self.occupied() != bishops {
+            return false;
+        }
+        let white = self.pieces_of(PieceKind::Bishop, Colour::White);
+        let black = self.pieces_of(PieceKind::Bishop, Colour::Black);
+        white != EMPTY && black != EMPTY
+            && ((white & LIGHT_SQUARES == EMPTY && black & !LIGHT_SQUARES == EMPTY)
+                || (white & !LIGHT_SQUARES == EMPTY && black & LIGHT_SQUARES == EMPTY))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::variant::Variant;
+
+    fn antichess(fen: &str) -> Board {
+        fen_parser::parse_variant(fen, Variant::Antichess).unwrap()
+    }
+
+    fn symbols(board: &Board) -> Vec<String> {
+        board.possible_moves().iter().map(|m| m.to_symbol()).collect()
+    }
+
+    #[test]
+    fn captures_are_compulsory() {
+        let board = antichess("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w - - 0 2");
+        assert_eq!(symbols(&board), vec!["e4d5"]);
+        // The king has no special status, it can walk into attack and be taken
+        let board = antichess("8/8/8/8/8/2k5/8/3K4 w - - 0 1");
+        assert!(symbols(&board).contains(&String::from("d1d2")));
+        let board = antichess("8/8/8/8/8/8/2k5/3K4 b - - 0 1");
+        assert_eq!(symbols(&board), vec!["c2d1"]);
+    }
+
+    #[test]
+    fn pawns_may_promote_to_a_king() {
+        let board = antichess("8/P7/8/8/8/8/8/7r w - - 0 1");
+        assert_eq!(symbols(&board), vec!["a7a8q", "a7a8r", "a7a8b", "a7a8n", "a7a8k"]);
+        assert!(!fen_parser::parse("8/P7/8/8/8/8/8/k6K w - - 0 1").unwrap().play_move(Move::from_symbol("a7a8k").unwrap()));
+    }
+
+    #[test]
+    fn losing_everything_or_being_stalemated_wins() {
+        assert_eq!(antichess("8/8/8/8/8/8/8/7r w - - 0 1").status(&[]), GameStatus::VariantWin);
+        assert_eq!(antichess("8/8/8/8/8/p7/P7/7r w - - 0 1").status(&[]), GameStatus::VariantWin);
+        assert_eq!(antichess("8/8/8/8/8/8/P7/7r w - - 0 1").status(&[]), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn bishops_on_different_colours_draw() {
+        assert_eq!(antichess("8/8/8/8/8/8/8/1bB5 w - - 0 1").status(&[]), GameStatus::InsufficientMaterial);
+        assert_eq!(antichess("8/8/8/8/8/8/8/b1B5 w - - 0 1").status(&[]), GameStatus::Ongoing);
+    }
+}
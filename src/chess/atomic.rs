@@ -0,0 +1,130 @@
+use super::bitboard::{BitboardIter, EMPTY, KING_ATTACKS};
+use super::board::Board;
+use super::colour::Colour;
+use super::piece::Piece;
+use super::piece_kind::PieceKind;
+use super::position::Position;
+use super::r#move::Move;
+
+impl Board {
+    // Kings never capture, and no move may blow up the mover's king or leave it in check unless the enemy king blows up
+    pub fn atomic_moves(&self) -> Vec<Move> {
+        let mut moves = self.pseudo_legal_moves();
+        let mut board = *self;
+        moves.retain(|m| {
+            let king_captures = self.get(m.from).map(|piece| piece.kind) == Some(PieceKind::King)
+                && self.get(m.to).is_some_and(|piece| piece.colour != self.turn);
+            if king_captures {
+                return false;
+            }
+            let undo = board.make_move(*m);
+            let legal = board.pieces_of(PieceKind::King, self.turn) != EMPTY
+                && (board.pieces_of(PieceKind::King, self.turn.opposite()) == EMPTY || !board.is_check(self.turn));
+            board.unmake_move(undo);
+            legal
+        });
+        moves
+    }
+
+    // Removes the piece on the capture square and every piece around it apart from pawns
+    pub fn explode(&mut self, centre: Position) -> [Option<(Piece, Position)>; 9] {
+        let mut exploded = [None; 9];
+        let index = centre.get_index().unwrap();
+        let blast = BitboardIter(KING_ATTACKS[index] & !self.pieces(PieceKind::Pawn)).chain(std::iter::once(index));
+        for (slot, pos) in blast.map(Position::from_index).enumerate() {
+            let piece = match self.get(pos) {
+                Some(piece) => piece,
+                None => continue,
+            };
+            match piece.kind {
+                PieceKind::Rook => self.update_can_castle(pos, piece.colour),
+                PieceKind::King => {
+                    self.set_can_castle(Board::castle_right(piece.colour, true), false);
+                    self.set_can_castle(Board::castle_right(piece.colour, false), false);
+                },
+                _ => (),
+            }
+            self.set(None, pos);
+            exploded[slot] = Some((piece, pos));
+        }
+        exploded
+    }
+
+    // A king next to the enemy king is never in check since capturing it would blow up both kings
+    pub fn is_atomic_check(&self, colour: Colour) -> bool {
+        let king = self.pieces_of(PieceKind::King, colour);
+        let kings_touch = BitboardIter(king).any(|index| KING_ATTACKS[index] & self.pieces_of(PieceKind::King, colour.opposite()) != EMPTY);
+        !kings_touch && BitboardIter(king).any(|index| self.is_square_attacked(index, colour.opposite()))
+    }
+
+    // Pieces next to a king can always be blown up, so only a bare king against a lone knight, bishop or rook
+    // or against two knights is safe
+    pub fn is_atomic_insufficient_material(&self) -> bool {
+        let army = |colour: Colour| PieceKind::ALL.iter()
+            .filter(|kind| **kind != PieceKind::King)
+            .fold(EMPTY, |army, kind| army | self.pieces_of(*kind, colour));
+        let (white, black) = (army(Colour::White), army(Colour::Black));
+        if white != EMPTY && black != EMPTY {
+            return false;
+        }
+        let army = white | black;
+        let knights = self.pieces(PieceKind::Knight);
+        (army.count_ones() <= 1 && self.pieces(PieceKind::Queen) | self.pieces(PieceKind::Pawn) == EMPTY)
+            || (army == knights && knights.count_ones() <= 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::check_make_unmake;
+    use crate::chess::fen_parser;
+    use crate::chess::game_status::GameStatus;
+    use crate::chess::variant::Variant;
+
+    fn atomic(fen: &str) -> Board {
+        fen_parser::parse_variant(fen, Variant::Atomic).unwrap()
+    }
+
+    fn has_move(board: &Board, symbol: &str) -> bool {
+        board.possible_moves().contains(&Move::from_symbol(symbol).unwrap())
+    }
+
+    #[test]
+    fn captures_explode_pieces_but_not_pawns() {
+        let mut board = atomic("4k3/8/2rbp3/3n4/8/8/3Q4/4K3 w - - 0 1");
+        assert!(board.play_move(Move::from_symbol("d2d5").unwrap()));
+        assert_eq!(board.to_fen(), "4k3/8/4p3/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn kings_never_capture_and_touching_kings_cancel_check() {
+        let board = atomic("8/8/8/8/8/8/3kq3/3K4 w - - 0 1");
+        assert!(!board.is_check(Colour::White));
+        assert!(!has_move(&board, "d1e2"));
+        assert!(has_move(&board, "d1e1"));
+    }
+
+    #[test]
+    fn exploding_a_king_ends_the_game() {
+        let own_king = atomic("7k/8/8/8/8/8/3rK3/3R4 w - - 0 1");
+        assert!(!has_move(&own_king, "d1d2"));
+        let mut board = atomic("4k3/4r3/8/8/8/8/8/4RK2 w - - 0 1");
+        assert!(board.play_move(Move::from_symbol("e1e7").unwrap()));
+        assert_eq!(board.status(&[]), GameStatus::VariantLoss);
+    }
+
+    #[test]
+    fn detects_insufficient_material() {
+        assert!(atomic("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").is_atomic_insufficient_material());
+        assert!(atomic("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1").is_atomic_insufficient_material());
+        assert!(!atomic("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").is_atomic_insufficient_material());
+        assert!(!atomic("4k3/8/8/8/8/8/8/2BNK3 w - - 0 1").is_atomic_insufficient_material());
+        assert!(!atomic("4kn2/8/8/8/8/8/8/3NK3 w - - 0 1").is_atomic_insufficient_material());
+    }
+
+    #[test]
+    fn unmake_restores_explosions() {
+        check_make_unmake("startpos", Variant::Atomic, 7, 20, 80);
+    }
+}
@@ -0,0 +1,165 @@
+use super::colour::Colour;
+use super::SIZE;
+use super::SQUARE_SIZE;
+
+// One bit per square, bit index is x + y * SIZE so a1 is bit 0 and h8 is bit 63
+pub type Bitboard = u64;
+
+pub const EMPTY: Bitboard = 0;
+pub const RANK_1: Bitboard = 0xff;
+pub const RANK_2: Bitboard = RANK_1 << SIZE;
+pub const RANK_3: Bitboard = RANK_1 << (2 * SIZE);
+pub const RANK_6: Bitboard = RANK_1 << (5 * SIZE);
+pub const RANK_8: Bitboard = RANK_1 << (7 * SIZE);
+// Light squares such as b1 and a2, used to tell whether bishops share a square colour
+pub const LIGHT_SQUARES: Bitboard = 0x55aa_55aa_55aa_55aa;
+
+// Ray directions as (x, y) steps, the first four increase the square index and the last four decrease it
+const DIRECTIONS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (-1, 1), (0, -1), (-1, -1), (-1, 0), (1, -1)];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 2, 4, 6];
+const BISHOP_DIRECTIONS: [usize; 4] = [1, 3, 5, 7];
+
+pub const KNIGHT_ATTACKS: [Bitboard; SQUARE_SIZE] = leaper_table(&[(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)]);
+pub const KING_ATTACKS: [Bitboard; SQUARE_SIZE] = leaper_table(&[(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)]);
+const WHITE_PAWN_ATTACKS: [Bitboard; SQUARE_SIZE] = leaper_table(&[(1, 1), (-1, 1)]);
+const BLACK_PAWN_ATTACKS: [Bitboard; SQUARE_SIZE] = leaper_table(&[(1, -1), (-1, -1)]);
+const RAYS: [[Bitboard; SQUARE_SIZE]; 8] = ray_table();
+
+const fn on_board(x: i8, y: i8) -> bool {
+    x >= 0 && x < SIZE && y >= 0 && y < SIZE
+}
+
+const fn leaper_table(steps: &[(i8, i8)]) -> [Bitboard; SQUARE_SIZE] {
+    let mut table = [EMPTY; SQUARE_SIZE];
+    let mut square = 0;
+    while square < SQUARE_SIZE {
+        let x = square as i8 % SIZE;
+        let y = square as i8 / SIZE;
+        let mut i = 0;
+        while i < steps.len() {
+            let (dx, dy) = steps[i];
+            if on_board(x + dx, y + dy) {
+                table[square] |= 1 << ((x + dx) + (y + dy) * SIZE);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn ray_table() -> [[Bitboard; SQUARE_SIZE]; 8] {
+    let mut table = [[EMPTY; SQUARE_SIZE]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dx, dy) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < SQUARE_SIZE {
+            let mut x = square as i8 % SIZE + dx;
+            let mut y = square as i8 / SIZE + dy;
+            while on_board(x, y) {
+                table[direction][square] |= 1 << (x + y * SIZE);
+                x += dx;
+                y += dy;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+pub fn square(index: usize) -> Bitboard {
+    1 << index
+}
+
+pub fn pawn_attacks(colour: Colour, index: usize) -> Bitboard {
+    match colour {
+        Colour::White => WHITE_PAWN_ATTACKS[index],
+        Colour::Black => BLACK_PAWN_ATTACKS[index],
+    }
+}
+
+// Follows the ray until the first blocker, which is included so captures can be masked out later
+fn ray_attacks(direction: usize, index: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[direction][index];
+    let blockers = ray & occupied;
+    if blockers == EMPTY {
+        return ray;
+    }
+    let first = if direction < 4 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][first as usize]
+}
+
+pub fn rook_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    ROOK_DIRECTIONS.iter().fold(EMPTY, |acc, d| acc | ray_attacks(*d, index, occupied))
+}
+
+pub fn bishop_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    BISHOP_DIRECTIONS.iter().fold(EMPTY, |acc, d| acc | ray_attacks(*d, index, occupied))
+}
+
+pub fn queen_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(index, occupied) | bishop_attacks(index, occupied)
+}
+
+// Squares strictly between two squares on the same rank, file or diagonal, empty otherwise
+pub fn between(a: usize, b: usize) -> Bitboard {
+    RAYS.iter()
+        .position(|ray| ray[a] & square(b) != EMPTY)
+        .map_or(EMPTY, |direction| ray_attacks(direction, a, square(b)) & !square(b))
+}
+
+// Iterates over the indices of the set bits from a1 to h8
+pub struct BitboardIter(pub Bitboard);
+
+impl Iterator for BitboardIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == EMPTY {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knight_attacks_from_corner() {
+        // a1 attacks b3 and c2
+        assert_eq!(KNIGHT_ATTACKS[0], square(17) | square(10));
+    }
+
+    #[test]
+    fn rook_attacks_stop_at_blockers() {
+        // Rook on a1 with a blocker on a4 and on c1
+        let attacks = rook_attacks(0, square(24) | square(2));
+        assert_eq!(attacks, square(8) | square(16) | square(24) | square(1) | square(2));
+    }
+
+    #[test]
+    fn bishop_attacks_stop_at_blockers() {
+        // Bishop on d4 with a blocker on f6
+        let attacks = bishop_attacks(27, square(45));
+        assert_eq!(attacks & square(45), square(45));
+        assert_eq!(attacks & square(54), EMPTY);
+        assert_eq!(attacks.count_ones(), 11);
+    }
+
+    #[test]
+    fn between_squares() {
+        assert_eq!(between(0, 3), square(1) | square(2));
+        assert_eq!(between(0, 27), square(9) | square(18));
+        assert_eq!(between(0, 17), EMPTY);
+    }
+}
@@ -1,24 +1,41 @@
+use super::bitboard::{self, Bitboard, BitboardIter, EMPTY, KING_ATTACKS, KNIGHT_ATTACKS};
+use super::castling::Castling;
 use super::colour::Colour;
 use super::piece::Piece;
 use super::r#move::Move;
 use super::position::Position;
 use super::piece_kind::PieceKind;
-use super::SQUARE_SIZE;
 use super::SIZE;
 use super::position_iter::PositionIter;
+use super::undo::Undo;
+use super::variant::Variant;
+use super::zobrist;
 use std::fmt;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Board {
-    pieces: [Option<Piece>; SQUARE_SIZE],
+    pieces: [Bitboard; 6],
+    colours: [Bitboard; 2],
     pub turn: Colour,
     pub castle_white_king_side: bool,
     pub castle_white_queen_side: bool,
     pub castle_black_king_side: bool,
     pub castle_black_queen_side: bool,
+    // File of the rook each castling right belongs to, indexed like the zobrist castling keys
+    pub castle_rook_files: [i8; 4],
+    // Chess960 castling is written as the king taking its own rook instead of moving two files
+    pub chess960: bool,
+    pub variant: Variant,
+    // Crazyhouse pieces in hand by colour then piece kind index
+    pub pockets: [[u8; 6]; 2],
+    // Crazyhouse pieces that started as pawns
+    pub promoted: Bitboard,
+    // Three-check checks given by each colour
+    pub checks: [u8; 2],
     pub en_passant: Option<Position>,
     pub half_move_number: usize,
     pub move_number: usize,
+    hash: u64,
 }
 
 impl fmt::Debug for Board {
@@ -32,7 +49,7 @@ impl fmt::Debug for Board {
             i += 1;
             if i == SIZE {
                 i = 0;
-                write!(f, "\n").unwrap();
+                writeln!(f).unwrap();
             }
         });
         fmt::Result::Ok(())
@@ -42,12 +59,32 @@ impl fmt::Debug for Board {
 impl Board {
 
     pub fn get(&self, position: Position) -> Option<Piece> {
-        self.pieces[position.get_index()?]
+        let mask = bitboard::square(position.get_index()?);
+        let colour = if self.colours[Colour::White.to_index()] & mask != EMPTY {
+            Colour::White
+        } else if self.colours[Colour::Black.to_index()] & mask != EMPTY {
+            Colour::Black
+        } else {
+            return None;
+        };
+        let kind = PieceKind::ALL.iter().find(|kind| self.pieces[kind.to_index()] & mask != EMPTY)?;
+        Some(Piece::new(*kind, colour))
     }
 
-    fn set(&mut self, piece: Option<Piece>, position: Position) -> Option<Option<Piece>> {
-        let temp = self.pieces[position.get_index()?];
-        self.pieces[position.get_index()?] = piece;
+    pub fn set(&mut self, piece: Option<Piece>, position: Position) -> Option<Option<Piece>> {
+        let temp = self.get(position);
+        let index = position.get_index()?;
+        let mask = bitboard::square(index);
+        if let Some(old) = temp {
+            self.pieces[old.kind.to_index()] &= !mask;
+            self.colours[old.colour.to_index()] &= !mask;
+            self.hash ^= zobrist::piece(old, index);
+        }
+        if let Some(new) = piece {
+            self.pieces[new.kind.to_index()] |= mask;
+            self.colours[new.colour.to_index()] |= mask;
+            self.hash ^= zobrist::piece(new, index);
+        }
         Some(temp)
     }
 
@@ -57,142 +94,421 @@ impl Board {
 
     // Copies the board and plays a move
     pub fn branch(&self, m: Move) -> Board {
-        let mut board = Board{
-            pieces: self.pieces.clone(),
-            turn: self.turn.clone(),
-            castle_black_king_side: self.castle_black_king_side,
-            castle_white_king_side: self.castle_white_king_side,
-            castle_black_queen_side: self.castle_black_queen_side,
-            castle_white_queen_side: self.castle_white_queen_side,
-            en_passant: self.en_passant,
-            half_move_number: self.half_move_number,
-            move_number: self.move_number,
-        };
+        let mut board = *self;
         board.play_move(m);
         board
     }
 
     pub fn new() -> Board {
-        let pieces: [Option<Piece>; SQUARE_SIZE] = [None; SQUARE_SIZE];
         Board{
-            pieces,
+            pieces: [EMPTY; 6],
+            colours: [EMPTY; 2],
             turn: Colour::White,
             castle_black_king_side: false,
             castle_black_queen_side: false,
             castle_white_king_side: false,
             castle_white_queen_side: false,
+            castle_rook_files: [SIZE - 1, 0, SIZE - 1, 0],
+            chess960: false,
+            variant: Variant::Standard,
+            pockets: [[0; 6]; 2],
+            promoted: EMPTY,
+            checks: [0; 2],
             en_passant: None,
             half_move_number: 0,
             move_number: 1,
+            hash: 0,
+        }
+    }
+
+    // Zobrist key of the position, kept up to date incrementally as moves are played
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // Builds the Zobrist key from scratch, needed after editing the public fields directly
+    pub fn compute_hash(&self) -> u64 {
+        let pieces = self.position_iter().fold(0, |hash, pos| {
+            match self.get(pos) {
+                Some(piece) => hash ^ zobrist::piece(piece, pos.get_index().unwrap()),
+                None => hash,
+            }
+        });
+        let turn = match self.turn {
+            Colour::White => 0,
+            Colour::Black => zobrist::BLACK_TO_MOVE,
+        };
+        pieces ^ turn ^ self.castling_hash() ^ self.en_passant_hash() ^ self.variant_hash()
+    }
+
+    pub fn refresh_hash(&mut self) {
+        self.hash = self.compute_hash();
+    }
+
+    fn castling_hash(&self) -> u64 {
+        (0..4).filter(|right| self.can_castle(*right)).fold(0, |hash, right| hash ^ zobrist::castling(right))
+    }
+
+    // State only some variants have, the crazyhouse pockets and promoted pieces and the three-check counts
+    fn variant_hash(&self) -> u64 {
+        match self.variant {
+            Variant::Crazyhouse => self.pocket_hash(),
+            Variant::ThreeCheck => [Colour::White, Colour::Black].iter()
+                .map(|colour| (*colour, self.checks[colour.to_index()]))
+                .filter(|(_, count)| *count > 0)
+                .fold(0, |hash, (colour, count)| hash ^ zobrist::checks(colour, count)),
+            _ => 0,
+        }
+    }
+
+    fn pocket_hash(&self) -> u64 {
+        let mut hash = BitboardIter(self.promoted).fold(0, |hash, index| hash ^ zobrist::promoted(index));
+        for colour in [Colour::White, Colour::Black].iter() {
+            for kind in PieceKind::ALL.iter() {
+                let count = self.pockets[colour.to_index()][kind.to_index()];
+                if count > 0 {
+                    hash ^= zobrist::pocket(*colour, *kind, count);
+                }
+            }
+        }
+        hash
+    }
+
+    // Index of a castling right into castle_rook_files and the zobrist castling keys
+    pub fn castle_right(colour: Colour, king_side: bool) -> usize {
+        match (colour, king_side) {
+            (Colour::White, true) => zobrist::CASTLE_WHITE_KING_SIDE,
+            (Colour::White, false) => zobrist::CASTLE_WHITE_QUEEN_SIDE,
+            (Colour::Black, true) => zobrist::CASTLE_BLACK_KING_SIDE,
+            (Colour::Black, false) => zobrist::CASTLE_BLACK_QUEEN_SIDE,
+        }
+    }
+
+    pub fn can_castle(&self, right: usize) -> bool {
+        match right {
+            zobrist::CASTLE_WHITE_KING_SIDE => self.castle_white_king_side,
+            zobrist::CASTLE_WHITE_QUEEN_SIDE => self.castle_white_queen_side,
+            zobrist::CASTLE_BLACK_KING_SIDE => self.castle_black_king_side,
+            _ => self.castle_black_queen_side,
+        }
+    }
+
+    pub fn set_can_castle(&mut self, right: usize, value: bool) {
+        match right {
+            zobrist::CASTLE_WHITE_KING_SIDE => self.castle_white_king_side = value,
+            zobrist::CASTLE_WHITE_QUEEN_SIDE => self.castle_white_queen_side = value,
+            zobrist::CASTLE_BLACK_KING_SIDE => self.castle_black_king_side = value,
+            _ => self.castle_black_queen_side = value,
+        }
+    }
+
+    pub fn home_rank(colour: Colour) -> i8 {
+        match colour {
+            Colour::White => 0,
+            Colour::Black => SIZE - 1,
+        }
+    }
+
+    // Whether the move takes a piece, en passant included
+    pub fn is_capture(&self, m: Move) -> bool {
+        let en_passant = self.get(m.from).map(|piece| piece.kind) == Some(PieceKind::Pawn) && Some(m.to) == self.en_passant;
+        m.drop.is_none() && self.castling(m).is_none() && (self.get(m.to).is_some() || en_passant)
+    }
+
+    // The castling a move makes, the king moves two files in standard chess and takes its own rook in Chess960
+    pub fn castling(&self, m: Move) -> Option<Castling> {
+        let king = self.get(m.from).filter(|piece| piece.kind == PieceKind::King)?;
+        if m.to.y != m.from.y || m.from.y != Self::home_rank(king.colour) {
+            return None;
         }
+        let rook_from = if self.chess960 {
+            Some(m.to).filter(|pos| self.get(*pos) == Some(Piece::new(PieceKind::Rook, king.colour)))?
+        } else if (m.to.x - m.from.x).abs() == 2 {
+            let right = Self::castle_right(king.colour, m.to.x > m.from.x);
+            Position::new(self.castle_rook_files[right], m.from.y)
+        } else {
+            return None;
+        };
+        Some(Castling::new(m.from, rook_from))
     }
 
-    pub fn is_checkmate(&self) -> bool {
-        self.possible_moves().len() == 0 && self.is_check(self.turn)
+    // The en passant file only counts when a pawn of the side to move could capture
+    fn en_passant_hash(&self) -> u64 {
+        match self.en_passant.and_then(|pos| Some((pos, pos.get_index()?))) {
+            Some((pos, index)) if bitboard::pawn_attacks(self.turn.opposite(), index) & self.pieces_of(PieceKind::Pawn, self.turn) != EMPTY => {
+                zobrist::en_passant_file(pos.x)
+            },
+            _ => 0,
+        }
     }
 
     // Modifies the board by playing a move returns if it is valid or not
+    // A pawn reaching the last rank must name its promotion and no other move may
     pub fn play_move(&mut self, m: Move) -> bool {
         let from_piece = self.get(m.from);
         let to_piece = self.get(m.to);
-        let result = 
-            from_piece.is_some() && from_piece.unwrap().colour == self.turn
-            && (to_piece.is_none() || to_piece.unwrap().colour != self.turn);
-        if !result { return false; }
+        let promotes = from_piece.map(|p| p.kind) == Some(PieceKind::Pawn) && (m.to.y == 0 || m.to.y == SIZE - 1);
+        let result = match m.drop {
+            Some(kind) => m.promote.is_none() && self.can_drop(kind, m.to),
+            None =>
+                from_piece.is_some() && from_piece.unwrap().colour == self.turn
+                && (to_piece.is_none() || to_piece.unwrap().colour != self.turn || self.castling(m).is_some())
+                && promotes == m.promote.is_some()
+                && m.promote.is_none_or(|kind| self.promotions().contains(&kind)),
+        };
+        if result {
+            self.make_move(m);
+        }
+        result
+    }
+
+    // Plays a move in place without checking it, the returned Undo takes it back with unmake_move
+    pub fn make_move(&mut self, m: Move) -> Undo {
+        if let Some(kind) = m.drop {
+            return self.make_drop(m, kind);
+        }
+        let from_piece = self.get(m.from);
+        let from = from_piece.expect("make_move needs a piece on the from square");
+        let castling = self.castling(m);
+        let capture_pos = if from.kind == PieceKind::Pawn && Some(m.to) == self.en_passant {
+            Position{x: m.to.x, y: m.from.y}
+        } else {
+            m.to
+        };
+        let to_piece = self.get(m.to).filter(|_| castling.is_none());
+        let captured = self.get(capture_pos).filter(|_| castling.is_none()).map(|piece| (piece, capture_pos));
+        let mut undo = self.undo(m, from, captured, castling);
+        self.hash ^= self.castling_hash() ^ self.en_passant_hash() ^ self.variant_hash();
+        if from.kind == PieceKind::Pawn || undo.captured.is_some() {
+            self.half_move_number = 0;
+        } else {
+            self.half_move_number += 1;
+        }
+        if self.turn == Colour::Black {
+            self.move_number += 1;
+        }
 
         // Castle
-        let from = from_piece.unwrap();
         match from.kind {
-            PieceKind::Rook => self.update_can_castle(m, from.colour),
-            PieceKind::King => self.play_king_move(m, from.colour),
+            PieceKind::Rook => self.update_can_castle(m.from, from.colour),
+            PieceKind::King => {
+                self.set_can_castle(Self::castle_right(from.colour, true), false);
+                self.set_can_castle(Self::castle_right(from.colour, false), false);
+            },
             _ => (),
         }
-        // Promotion
-        if from.kind == PieceKind::Pawn && m.to.y * 2 == (SIZE - 1) * (1 + Self::get_pawn_direction(self.turn)) {
-            self.set(Some(Piece::new(m.promote.unwrap_or(PieceKind::Queen), self.turn)), m.to);
-        } else {
-            self.set(from_piece, m.to);
+        if let Some(Piece{kind: PieceKind::Rook, colour}) = to_piece {
+            self.update_can_castle(m.to, colour);
+        }
+        let promotes = from.kind == PieceKind::Pawn && m.to.y * 2 == (SIZE - 1) * (1 + Self::get_pawn_direction(self.turn));
+        if self.variant == Variant::Crazyhouse {
+            self.update_pockets(m, captured, promotes);
         }
+        if let Some(castling) = castling {
+            // The king and rook may swap squares so both leave before either lands
+            self.set(None, castling.king_from);
+            self.set(None, castling.rook_from);
+            self.set(from_piece, castling.king_to);
+            self.set(Some(Piece::new(PieceKind::Rook, from.colour)), castling.rook_to);
+        } else {
+            // Promotion
+            if promotes {
+                self.set(Some(Piece::new(m.promote.expect("make_move needs the promotion piece"), self.turn)), m.to);
+            } else {
+                self.set(from_piece, m.to);
+            }
 
-        // En passant
-        if self.en_passant.is_some() && m.to == self.en_passant.unwrap() {
-            self.set(None, Position{x: m.to.x, y: m.from.y});
+            // En passant
+            if capture_pos != m.to {
+                self.set(None, capture_pos);
+            }
+            self.set(None, m.from);
+        }
+        if self.variant == Variant::Atomic && captured.is_some() {
+            undo.exploded = self.explode(m.to);
         }
-        if from.kind == PieceKind::Pawn && (m.to.y - m.from.y == 2 || m.from.y - m.to.y == 2) {
+        // Horde pawns stepping twice from the first rank cannot be taken en passant
+        let from_start = m.from.y == 1 || m.from.y == SIZE - 2;
+        if from.kind == PieceKind::Pawn && from_start && (m.to.y - m.from.y == 2 || m.from.y - m.to.y == 2) {
             self.en_passant = Some(m.from.average(m.to));
         } else {
             self.en_passant = None;
         }
 
-        self.set(None, m.from);
         self.turn = self.turn.opposite();
-        result
+        if self.variant == Variant::ThreeCheck && self.is_check(self.turn) {
+            self.checks[self.turn.opposite().to_index()] += 1;
+        }
+        self.hash ^= zobrist::BLACK_TO_MOVE ^ self.castling_hash() ^ self.en_passant_hash() ^ self.variant_hash();
+        undo
     }
 
-    fn update_can_castle(&mut self, m: Move, colour: Colour) {
-        if m.from.x < SIZE / 2 {
-            match colour {
-                Colour::White => self.castle_white_queen_side = false,
-                Colour::Black => self.castle_black_queen_side = false,
-            }
+    fn undo(&self, m: Move, moved: Piece, captured: Option<(Piece, Position)>, castling: Option<Castling>) -> Undo {
+        Undo{
+            m,
+            moved,
+            captured,
+            castling,
+            castle_white_king_side: self.castle_white_king_side,
+            castle_white_queen_side: self.castle_white_queen_side,
+            castle_black_king_side: self.castle_black_king_side,
+            castle_black_queen_side: self.castle_black_queen_side,
+            en_passant: self.en_passant,
+            half_move_number: self.half_move_number,
+            move_number: self.move_number,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            checks: self.checks,
+            exploded: [None; 9],
+            hash: self.hash,
+        }
+    }
+
+    // Captured pieces go to the capturer's pocket, promoted ones as pawns, and the promoted mark follows the piece
+    fn update_pockets(&mut self, m: Move, captured: Option<(Piece, Position)>, promotes: bool) {
+        if let Some((piece, pos)) = captured {
+            let mask = bitboard::square(pos.get_index().unwrap());
+            let kind = if self.promoted & mask != EMPTY { PieceKind::Pawn } else { piece.kind };
+            self.pockets[self.turn.to_index()][kind.to_index()] += 1;
+            self.promoted &= !mask;
+        }
+        let from = bitboard::square(m.from.get_index().unwrap());
+        let to = bitboard::square(m.to.get_index().unwrap());
+        if promotes || self.promoted & from != EMPTY {
+            self.promoted = (self.promoted & !from) | to;
+        }
+    }
+
+    fn make_drop(&mut self, m: Move, kind: PieceKind) -> Undo {
+        let piece = Piece::new(kind, self.turn);
+        let undo = self.undo(m, piece, None, None);
+        self.hash ^= self.en_passant_hash() ^ self.variant_hash();
+        self.pockets[self.turn.to_index()][kind.to_index()] -= 1;
+        self.set(Some(piece), m.to);
+        if kind == PieceKind::Pawn {
+            self.half_move_number = 0;
         } else {
-            match colour {
-                Colour::White => self.castle_white_king_side = false,
-                Colour::Black => self.castle_black_king_side = false,
-            }
+            self.half_move_number += 1;
+        }
+        if self.turn == Colour::Black {
+            self.move_number += 1;
         }
+        self.en_passant = None;
+        self.turn = self.turn.opposite();
+        self.hash ^= zobrist::BLACK_TO_MOVE ^ self.en_passant_hash() ^ self.variant_hash();
+        undo
     }
 
-    fn play_king_move(&mut self, m: Move, colour: Colour) {
-        let king_delta = m.to.x - m.from.x;
-        if king_delta.abs() == 2 {
-            let x = 7 * (1 + king_delta / 2) / 2;
-            let pos = Position::new(x, m.from.y);
-            let rook = self.set(None, pos).unwrap();
-            self.set(
-                rook,
-                Position::new(m.from.x + king_delta / 2, m.from.y)
-            );
+    // Passes the turn without moving, for null move pruning. There is no undo, the caller keeps a copy of the board
+    pub fn make_null_move(&mut self) {
+        self.hash ^= self.en_passant_hash();
+        self.en_passant = None;
+        self.half_move_number += 1;
+        self.turn = self.turn.opposite();
+        self.hash ^= zobrist::BLACK_TO_MOVE;
+    }
+
+    // A crazyhouse piece in hand can go on any empty square except pawns on the first and last ranks
+    fn can_drop(&self, kind: PieceKind, to: Position) -> bool {
+        self.variant == Variant::Crazyhouse
+            && self.pockets[self.turn.to_index()][kind.to_index()] > 0
+            && !to.is_off_board()
+            && self.get(to).is_none()
+            && (kind != PieceKind::Pawn || (to.y != 0 && to.y != SIZE - 1))
+    }
+
+    // Takes back the move made by make_move, restoring the board exactly
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let m = undo.m;
+        for (piece, pos) in undo.exploded.iter().flatten() {
+            self.set(Some(*piece), *pos);
         }
-        match colour {
-            Colour::White => {self.castle_white_king_side = false; self.castle_white_queen_side = false;},
-            Colour::Black => {self.castle_black_king_side = false; self.castle_black_queen_side = false;},
+        if m.drop.is_some() {
+            self.set(None, m.to);
+        } else if let Some(castling) = undo.castling {
+            self.set(None, castling.king_to);
+            self.set(None, castling.rook_to);
+            self.set(Some(undo.moved), castling.king_from);
+            self.set(Some(Piece::new(PieceKind::Rook, undo.moved.colour)), castling.rook_from);
+        } else {
+            self.set(None, m.to);
+            self.set(Some(undo.moved), m.from);
+            if let Some((piece, pos)) = undo.captured {
+                self.set(Some(piece), pos);
+            }
         }
+        self.castle_white_king_side = undo.castle_white_king_side;
+        self.castle_white_queen_side = undo.castle_white_queen_side;
+        self.castle_black_king_side = undo.castle_black_king_side;
+        self.castle_black_queen_side = undo.castle_black_queen_side;
+        self.en_passant = undo.en_passant;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+        self.checks = undo.checks;
+        self.half_move_number = undo.half_move_number;
+        self.move_number = undo.move_number;
+        self.turn = self.turn.opposite();
+        self.hash = undo.hash;
     }
 
-    pub fn parse_moves(&mut self, moves: &str) -> bool {
-        moves == "" || moves.split(' ').map(|move_symbol| {
-            let m = Move::from_symbol(move_symbol);
-            m.is_some() && self.play_move(m.unwrap())
-        }).all(|x| x)
+    // Removes the castling right of a rook leaving or being captured on its starting square
+    pub fn update_can_castle(&mut self, pos: Position, colour: Colour) {
+        if pos.y != Self::home_rank(colour) {
+            return;
+        }
+        for king_side in [true, false].iter() {
+            let right = Self::castle_right(colour, *king_side);
+            if self.castle_rook_files[right] == pos.x {
+                self.set_can_castle(right, false);
+            }
+        }
     }
 
     pub fn position_iter(&self) -> PositionIter {
         PositionIter::new()
     }
 
-    // Finds the king and checks if any pieces are attacking it by calling is_king_in_check
+    // Every piece of this kind whatever its colour
+    pub fn pieces(&self, kind: PieceKind) -> Bitboard {
+        self.pieces[kind.to_index()]
+    }
+
+    pub fn occupied_by(&self, colour: Colour) -> Bitboard {
+        self.colours[colour.to_index()]
+    }
+
+    pub fn pieces_of(&self, kind: PieceKind, colour: Colour) -> Bitboard {
+        self.pieces[kind.to_index()] & self.colours[colour.to_index()]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colours[Colour::White.to_index()] | self.colours[Colour::Black.to_index()]
+    }
+
+    // Finds the king and checks if any pieces are attacking it by calling is_square_attacked
     pub fn is_check(&self, colour: Colour) -> bool {
-        self.position_iter().any(
-            |pos| {
-                let piece = self.get(pos);
-                piece.and_then(|p| { Some(p.kind == PieceKind::King && p.colour == colour) } )
-                    .unwrap_or(false)
-                    && self.is_king_in_check(pos)
-            }
-        )
+        match self.variant {
+            Variant::Atomic => self.is_atomic_check(colour),
+            // The king is an ordinary piece in antichess
+            Variant::Antichess => false,
+            _ => BitboardIter(self.pieces_of(PieceKind::King, colour)).any(|index| self.is_square_attacked(index, colour.opposite())),
+        }
+    }
+
+    // Checks if any piece of colour by attacks the square, the square does not need to be occupied
+    // Atomic kings cannot capture so they attack nothing
+    pub fn is_square_attacked(&self, index: usize, by: Colour) -> bool {
+        self.is_square_attacked_through(index, by, self.occupied())
     }
 
-    fn is_king_in_check(&self, pos: Position) -> bool {
-        let colour = self.get(pos).unwrap().colour;
-        let direction = Self::get_pawn_direction(colour);
-        self.get(Position::new(pos.x + 1, pos.y + direction)) == Some(Piece::new(PieceKind::Pawn, colour.opposite()))
-        || self.get(Position::new(pos.x - 1, pos.y + direction)) == Some(Piece::new(PieceKind::Pawn, colour.opposite()))
-        || vec![PieceKind::Bishop, PieceKind::Knight, PieceKind::Queen, PieceKind::Rook, PieceKind::King].iter().any(|kind| {
-            self.move_map(pos, *kind).iter().any(|m| {
-                self.get(m.to) == Some(Piece::new(*kind, colour.opposite()))
-            })
-        })
+    // As is_square_attacked with sliders only blocked by the given occupancy
+    fn is_square_attacked_through(&self, index: usize, by: Colour, occupied: Bitboard) -> bool {
+        let queens = self.pieces_of(PieceKind::Queen, by);
+        bitboard::pawn_attacks(by.opposite(), index) & self.pieces_of(PieceKind::Pawn, by) != EMPTY
+            || KNIGHT_ATTACKS[index] & self.pieces_of(PieceKind::Knight, by) != EMPTY
+            || (self.variant != Variant::Atomic && KING_ATTACKS[index] & self.pieces_of(PieceKind::King, by) != EMPTY)
+            || bitboard::bishop_attacks(index, occupied) & (self.pieces_of(PieceKind::Bishop, by) | queens) != EMPTY
+            || bitboard::rook_attacks(index, occupied) & (self.pieces_of(PieceKind::Rook, by) | queens) != EMPTY
     }
 
     fn get_pawn_direction(colour: Colour) -> i8 {
@@ -202,119 +518,348 @@ impl Board {
         }
     }
 
-    // Returns a vector of all possible moves of the current player
-    pub fn possible_moves(&self) -> Vec<Move> {
-        self.position_iter().flat_map(|pos| {
-            let piece = self.get(pos);
-            if piece.is_none() || piece.unwrap().colour != self.turn  {
-                return Vec::default().into_iter();
-            }
-            let Piece{kind, colour: _} = piece.unwrap();
-            self.move_map(pos, kind).into_iter()
-        }).filter(
-            |m| {
-                !self.branch(*m).is_check(self.turn)
+    // Moves that do not leave the king in check
+    pub fn standard_moves(&self) -> Vec<Move> {
+        let mut moves = self.pseudo_legal_moves();
+        let mut board = *self;
+        moves.retain(|m| {
+            let undo = board.make_move(*m);
+            let legal = !board.is_check(self.turn);
+            board.unmake_move(undo);
+            legal
+        });
+        moves
+    }
+
+    // Moves that follow the piece movement rules but may leave the king in check
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut out = Vec::with_capacity(64);
+        let own = self.colours[self.turn.to_index()];
+        let occupied = self.occupied();
+        for kind in PieceKind::ALL.iter() {
+            for from in BitboardIter(self.pieces_of(*kind, self.turn)) {
+                let targets = match kind {
+                    PieceKind::Pawn => self.pawn_targets(from, occupied),
+                    _ => Self::attacks(*kind, from, self.turn, occupied) & !own,
+                };
+                let from = Position::from_index(from);
+                for to in BitboardIter(targets).map(Position::from_index) {
+                    if *kind == PieceKind::Pawn && (to.y == 0 || to.y == SIZE - 1) {
+                        out.extend(self.promotions().iter().map(|promote| Move{from, to, promote: Some(*promote), drop: None}));
+                    } else {
+                        out.push(Move::new(from, to));
+                    }
+                }
             }
-        ).collect()
+        }
+        out.extend(self.castle_moves());
+        out.extend(self.drop_moves());
+        out
     }
 
-    // fn castle_moves(&self, pos: Position, kind: PieceKind) -> Vec<Move> {
-        
-    // }
+    fn drop_moves(&self) -> Vec<Move> {
+        let mut out = Vec::default();
+        if self.variant != Variant::Crazyhouse {
+            return out;
+        }
+        let empty = !self.occupied();
+        for kind in PieceKind::ALL.iter().filter(|kind| self.pockets[self.turn.to_index()][kind.to_index()] > 0) {
+            let targets = if *kind == PieceKind::Pawn { empty & !bitboard::RANK_1 & !bitboard::RANK_8 } else { empty };
+            out.extend(BitboardIter(targets).map(|to| Move::new_drop(*kind, Position::from_index(to))));
+        }
+        out
+    }
 
-    fn move_map(&self, pos: Position, kind: PieceKind) -> Vec<Move> {
+    fn attacks(kind: PieceKind, index: usize, colour: Colour, occupied: Bitboard) -> Bitboard {
         match kind {
-            PieceKind::Pawn => self.pawn_moves(pos),
-            PieceKind::Knight => self.knight_moves(pos),
-            PieceKind::Queen => self.queen_moves(pos),
-            PieceKind::King => self.king_moves(pos),
-            PieceKind::Bishop => self.bishop_moves(pos),
-            PieceKind::Rook => self.rook_moves(pos),
+            PieceKind::Pawn => bitboard::pawn_attacks(colour, index),
+            PieceKind::Knight => KNIGHT_ATTACKS[index],
+            PieceKind::King => KING_ATTACKS[index],
+            PieceKind::Bishop => bitboard::bishop_attacks(index, occupied),
+            PieceKind::Rook => bitboard::rook_attacks(index, occupied),
+            PieceKind::Queen => bitboard::queen_attacks(index, occupied),
         }
     }
 
-    fn pawn_moves(&self, pos: Position) -> Vec<Move> {
+    fn pawn_targets(&self, index: usize, occupied: Bitboard) -> Bitboard {
+        let from = bitboard::square(index);
+        let (single, double) = match self.turn {
+            Colour::White => {
+                // Horde pawns on the first rank may also step twice
+                let double_from = if self.variant == Variant::Horde { bitboard::RANK_2 | bitboard::RANK_3 } else { bitboard::RANK_3 };
+                let single = (from << SIZE) & !occupied;
+                (single, (single & double_from) << SIZE & !occupied)
+            },
+            Colour::Black => {
+                let single = (from >> SIZE) & !occupied;
+                (single, (single & bitboard::RANK_6) >> SIZE & !occupied)
+            },
+        };
+        let en_passant = self.en_passant.and_then(|pos| pos.get_index()).map_or(EMPTY, bitboard::square);
+        let enemy = self.colours[self.turn.opposite().to_index()];
+        single | double | (bitboard::pawn_attacks(self.turn, index) & (enemy | en_passant))
+    }
+
+    // Castling requires the right to castle, the rook still on its square, every square either piece crosses or lands on
+    // empty apart from the king and rook themselves, and the king must not be in check or pass through an attacked square.
+    // Landing in check is filtered by possible_moves
+    fn castle_moves(&self) -> Vec<Move> {
         let mut out = Vec::default();
-        let Position{x, y} = pos;
-        let direction = Self::get_pawn_direction(self.turn);
-        let mut f = |new_pos: Position| -> bool {
-            if self.get(new_pos).is_none() {
-                out.push(Move::new(pos, new_pos));
-                return true;
-            }
-            false
+        let y = Self::home_rank(self.turn);
+        let home = bitboard::RANK_1 << (y * SIZE);
+        let king_index = match BitboardIter(self.pieces_of(PieceKind::King, self.turn) & home).next() {
+            Some(index) => index,
+            None => return out,
         };
-        if f(Position{x, y: y + direction}) {
-            if y == SIZE - 2 || y == 1 {
-                f(Position{x, y: y + direction * 2});
-            }
+        if self.is_check(self.turn) {
+            return out;
         }
-        if self.en_passant.is_some() {
-            let new_pos = Position{x: x + 1, y: y + direction};
-            if pos == self.en_passant.unwrap() {
-                out.push(Move::new(pos, new_pos));
+        let king_pos = Position::from_index(king_index);
+        for king_side in [true, false].iter() {
+            let right = Self::castle_right(self.turn, *king_side);
+            let rook_pos = Position::new(self.castle_rook_files[right], y);
+            if !self.can_castle(right) || self.get(rook_pos) != Some(Piece::new(PieceKind::Rook, self.turn)) {
+                continue;
             }
-            let new_pos = Position{x: x - 1, y: y + direction};
-            if pos == self.en_passant.unwrap() {
-                out.push(Move::new(pos, new_pos));
+            let castling = Castling::new(king_pos, rook_pos);
+            if self.can_castle_with(castling) {
+                out.push(Move::new(king_pos, if self.chess960 { rook_pos } else { castling.king_to }));
             }
         }
-        let mut g = |new_pos: Position| -> bool {
-            if self.get(new_pos).is_some() && self.get(new_pos).unwrap().colour != self.get(pos).unwrap().colour {
-                out.push(Move::new(pos, new_pos));
-                return true;
-            }
-            false
-        };
-        g(Position{x: x + 1, y: y + direction});
-        g(Position{x: x - 1, y: y + direction});
         out
     }
 
-    fn knight_moves(&self, pos: Position) -> Vec<Move> {
-        self.general_moves(pos, 1, vec![1, 2, -1, -2, -1, 2, 1, -2])
+    fn can_castle_with(&self, castling: Castling) -> bool {
+        let index = |pos: Position| pos.get_index().unwrap();
+        let king_path = bitboard::between(index(castling.king_from), index(castling.king_to)) | bitboard::square(index(castling.king_to));
+        let rook_path = bitboard::between(index(castling.rook_from), index(castling.rook_to)) | bitboard::square(index(castling.rook_to));
+        let others = self.occupied() & !bitboard::square(index(castling.king_from)) & !bitboard::square(index(castling.rook_from));
+        // An atomic king is safe anywhere next to the enemy king
+        let safe = if self.variant == Variant::Atomic {
+            BitboardIter(self.pieces_of(PieceKind::King, self.turn.opposite())).fold(EMPTY, |safe, index| safe | KING_ATTACKS[index])
+        } else {
+            EMPTY
+        };
+        // The king cannot shelter behind itself from a slider on its own line
+        let without_king = self.occupied() & !bitboard::square(index(castling.king_from));
+        let crossed = bitboard::between(index(castling.king_from), index(castling.king_to));
+        (king_path | rook_path) & others == EMPTY
+            && !BitboardIter(crossed & !safe).any(|square| self.is_square_attacked_through(square, self.turn.opposite(), without_king))
     }
+}
+
+// Plays seeded random games, making and unmaking every legal move on the way and finally unwinding the whole game,
+// the incremental hash must match a recompute and every unmake must restore the board exactly
+#[cfg(test)]
+pub fn check_make_unmake(fen: &str, variant: Variant, seed: u64, games: usize, plies: usize) {
+    use super::fen_parser;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
 
-    fn rook_moves(&self, pos: Position) -> Vec<Move> {
-        self.general_moves(pos, SIZE - 1, vec![1, 0, -1, 0])
+    let mut rng = StdRng::seed_from_u64(seed);
+    let start = fen_parser::parse_variant(fen, variant).unwrap();
+    assert_eq!(start.hash(), start.compute_hash());
+    for _ in 0..games {
+        let mut board = start;
+        let mut undos = Vec::default();
+        for _ in 0..plies {
+            let moves = board.possible_moves();
+            for m in moves.iter() {
+                let before = board;
+                let undo = board.make_move(*m);
+                assert_eq!(board.hash(), board.compute_hash(), "after {} in {}", m, before.to_fen());
+                assert_eq!(board.hash(), before.branch(*m).hash());
+                board.unmake_move(undo);
+                assert_eq!(board, before, "after {}", m);
+            }
+            match moves.choose(&mut rng) {
+                Some(m) => undos.push(board.make_move(*m)),
+                None => break,
+            }
+        }
+        while let Some(undo) = undos.pop() {
+            board.unmake_move(undo);
+        }
+        assert_eq!(board, start, "unwinding {}", fen);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::game_record::GameRecord;
 
-    fn queen_moves(&self, pos: Position) -> Vec<Move> {
-        self.general_moves(pos, SIZE - 1, vec![1, 1, 0, -1, -1, 0, 1, -1])
+    fn castles(fen: &str) -> Vec<String> {
+        let board = fen_parser::parse(fen).unwrap();
+        let king = board.position_iter().find(|pos| board.get(*pos) == Some(Piece::new(PieceKind::King, board.turn))).unwrap();
+        board.possible_moves().into_iter()
+            .filter(|m| m.from == king && (m.to.x - m.from.x).abs() == 2)
+            .map(|m| m.to_symbol())
+            .collect()
     }
 
-    fn king_moves(&self, pos: Position) -> Vec<Move> {
-        self.general_moves(pos, 1, vec![1, 1, 0, -1, -1, 0, 1, -1])
+    #[test]
+    fn castles_both_sides_when_clear() {
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), vec!["e1g1", "e1c1"]);
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"), vec!["e8g8", "e8c8"]);
     }
 
-    fn bishop_moves(&self, pos: Position) -> Vec<Move> {
-        self.general_moves(pos, SIZE - 1, vec![1, 1, -1, -1])
+    #[test]
+    fn no_castle_without_rights() {
+        assert!(castles("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1").is_empty());
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w Q - 0 1"), vec!["e1c1"]);
     }
 
-    fn general_moves(&self, pos: Position, distance: i8, matrix: Vec<i8>) -> Vec<Move> {
-        let mut out = Vec::default();
-        let colour = self.get(pos).unwrap().colour;
-        let Position{x, y} = pos;
-        for i in 0..matrix.len() + 1 {
-            let del_x = matrix.get(i % matrix.len()).unwrap();
-            let del_y = matrix.get((i + 1) % matrix.len()).unwrap();
-            for d in 1..(distance + 1) {
-                let position = Position{x: x + *del_x * d, y: y + *del_y * d};
-                if position.is_off_board() {
-                    break;
-                }
-                if self.get(position).is_none() {
-                    let m = Move::new(pos, position);
-                    out.push(m);
-                    continue;
-                }
-                if self.get(position).unwrap().colour != colour {
-                    let m = Move::new(pos, position);
-                    out.push(m);
-                }
-                break;
-            }
-        };
-        out
+    #[test]
+    fn no_castle_through_pieces() {
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/RN2K1NR w KQkq - 0 1"), Vec::<String>::new());
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R2QK2R w KQkq - 0 1"), vec!["e1g1"]);
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3KB1R w KQkq - 0 1"), vec!["e1c1"]);
+    }
+
+    #[test]
+    fn no_castle_out_of_check() {
+        assert!(castles("r3k2r/8/8/8/4r3/8/8/R3K2R w KQkq - 0 1").is_empty());
+    }
+
+    #[test]
+    fn no_castle_through_attacked_square() {
+        assert_eq!(castles("r3k2r/8/8/8/5r2/8/8/R3K2R w KQkq - 0 1"), vec!["e1c1"]);
+        assert_eq!(castles("r3k2r/8/8/8/3r4/8/8/R3K2R w KQkq - 0 1"), vec!["e1g1"]);
+    }
+
+    #[test]
+    fn no_castle_into_check() {
+        assert_eq!(castles("r3k2r/8/8/8/6r1/8/8/R3K2R w KQkq - 0 1"), vec!["e1c1"]);
+        assert_eq!(castles("r3k2r/8/8/8/2r5/8/8/R3K2R w KQkq - 0 1"), vec!["e1g1"]);
+    }
+
+    #[test]
+    fn castle_with_attacked_rook_path() {
+        // Only the squares the king crosses must be safe, b1 may be attacked
+        assert_eq!(castles("r3k2r/8/8/8/1r6/8/8/R3K2R w KQkq - 0 1"), vec!["e1g1", "e1c1"]);
+    }
+
+    #[test]
+    fn castle_moves_rook() {
+        let mut board = fen_parser::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(board.play_move(Move::from_symbol("e1g1").unwrap()));
+        assert_eq!(board.get(Position::from_symbol("f1").unwrap()), Some(Piece::new(PieceKind::Rook, Colour::White)));
+        assert_eq!(board.get(Position::from_symbol("h1").unwrap()), None);
+        assert!(board.play_move(Move::from_symbol("e8c8").unwrap()));
+        assert_eq!(board.get(Position::from_symbol("d8").unwrap()), Some(Piece::new(PieceKind::Rook, Colour::Black)));
+        assert_eq!(board.get(Position::from_symbol("a8").unwrap()), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn captured_rook_loses_castle_right() {
+        let mut board = fen_parser::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(board.play_move(Move::from_symbol("a1a8").unwrap()));
+        assert!(!board.castle_black_queen_side);
+        assert!(!board.castle_white_queen_side);
+        assert!(board.castle_black_king_side);
+        assert_eq!(castles("4k2r/8/8/8/8/8/8/4K2R b Kk - 0 1"), vec!["e8g8"]);
+    }
+
+    fn castles960(fen: &str) -> Vec<String> {
+        let board = fen_parser::parse(fen).unwrap();
+        board.possible_moves().into_iter()
+            .filter(|m| board.castling(*m).is_some())
+            .map(|m| m.to_symbol())
+            .collect()
+    }
+
+    #[test]
+    fn chess960_castles_by_taking_own_rook() {
+        assert_eq!(castles960("rk5r/8/8/8/8/8/8/RK5R w KQkq - 0 1"), vec!["b1h1", "b1a1"]);
+        let mut board = fen_parser::parse("rk5r/8/8/8/8/8/8/RK5R w KQkq - 0 1").unwrap();
+        let before = board;
+        let undo = board.make_move(Move::from_symbol("b1a1").unwrap());
+        assert_eq!(board.to_fen(), "rk5r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+        board.unmake_move(undo);
+        assert_eq!(board, before);
+        assert!(board.play_move(Move::from_symbol("b1h1").unwrap()));
+        assert_eq!(board.to_fen(), "rk5r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    }
+
+    #[test]
+    fn chess960_castling_needs_a_clear_path() {
+        // The king stays on g1 and only the rook moves
+        assert_eq!(castles960("4k3/8/8/8/8/8/8/4R1KR w H - 0 1"), vec!["g1h1"]);
+        // The king would land on the knight on c1
+        assert!(castles960("4k3/8/8/8/8/8/8/RNNK4 w A - 0 1").is_empty());
+        // Every square the king crosses or lands on must be safe
+        assert!(castles960("3rk3/8/8/8/8/8/8/1R2K3 w B - 0 1").is_empty());
+        assert!(castles960("2r1k3/8/8/8/8/8/8/1R1K4 w B - 0 1").is_empty());
+        assert_eq!(castles960("r3k3/8/8/8/8/8/8/1R1K4 w B - 0 1"), vec!["d1b1"]);
+        // Only the rook crosses the attacked b1 square
+        assert_eq!(castles960("1r2k3/8/8/8/8/8/8/R1K5 w A - 0 1"), vec!["c1a1"]);
+    }
+
+    #[test]
+    fn incremental_hash_matches_recompute() {
+        check_make_unmake("startpos", Variant::Standard, 3, 20, 80);
+        check_make_unmake("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", Variant::Standard, 3, 20, 80);
+        check_make_unmake("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", Variant::Standard, 3, 20, 80);
+    }
+
+    #[test]
+    fn hash_identifies_transpositions() {
+        let start = fen_parser::parse("startpos").unwrap();
+        let hash = |moves: &str| GameRecord::from_moves(start, moves).unwrap().board().hash();
+        assert_eq!(hash("g1f3 g8f6 f3g1 f6g8"), start.hash());
+        assert_eq!(hash("e2e4 e7e5 g1f3"), hash("g1f3 e7e5 e2e4"));
+    }
+
+    #[test]
+    fn null_move_keeps_hash() {
+        let mut board = fen_parser::parse("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3").unwrap();
+        board.make_null_move();
+        assert_eq!(board.turn, Colour::White);
+        assert_eq!(board.en_passant, None);
+        assert_eq!(board.hash(), board.compute_hash());
+        assert_eq!(board.hash(), fen_parser::parse("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 3").unwrap().hash());
+    }
+
+    #[test]
+    fn hash_distinguishes_side_castling_and_en_passant() {
+        let hash = |fen: &str| fen_parser::parse(fen).unwrap().hash();
+        let base = hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq - 0 1");
+        assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R b KQkq - 0 1"));
+        assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kkq - 0 1"));
+        assert_ne!(base, hash("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1"));
+        // No pawn can take en passant so the square does not change the position
+        assert_eq!(hash("r3k2r/8/8/3p4/8/8/8/R3K2R w KQkq - 0 1"), hash("r3k2r/8/8/3p4/8/8/8/R3K2R w KQkq d6 0 1"));
+    }
+
+    #[test]
+    fn unmake_restores_board() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4rrk1/pbbp2p1/1ppnp3/3n1pqp/3N1PQP/1PPNP3/PBBP2P1/4RRK1 w Ff - 0 1",
+        ];
+        for fen in fens.iter() {
+            check_make_unmake(fen, Variant::Standard, 4, 20, 80);
+        }
+    }
+
+    #[test]
+    fn crazyhouse_drops_keep_hash_and_unmake() {
+        check_make_unmake("startpos", Variant::Crazyhouse, 6, 20, 80);
+    }
+
+    #[test]
+    fn crazyhouse_captures_fill_the_pocket() {
+        let mut board = fen_parser::parse_variant("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", Variant::Crazyhouse).unwrap();
+        assert!(board.play_move(Move::from_symbol("e4b7").unwrap()));
+        assert_eq!(board.pockets[Colour::Black.to_index()][PieceKind::Pawn.to_index()], 1);
+        assert!(board.play_move(Move::from_symbol("a2b2").unwrap()));
+        assert!(board.play_move(Move::from_symbol("P@d4").unwrap()));
+        assert!(!board.play_move(Move::from_symbol("P@a8").unwrap()));
+        assert!(!board.play_move(Move::from_symbol("N@e8").unwrap()));
+        assert_eq!(board.to_fen(), "4k3/1b6/8/8/3p4/8/1Kp5/8[P] w - - 0 3");
+    }}
@@ -0,0 +1,27 @@
+use super::position::Position;
+
+// Where the king and rook start and end when castling, the king always lands on the g or c file
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Castling {
+    pub king_from: Position,
+    pub king_to: Position,
+    pub rook_from: Position,
+    pub rook_to: Position,
+}
+
+impl Castling {
+    pub fn new(king_from: Position, rook_from: Position) -> Self {
+        let y = king_from.y;
+        let (king_x, rook_x) = if rook_from.x > king_from.x { (6, 5) } else { (2, 3) };
+        Self {
+            king_from,
+            king_to: Position::new(king_x, y),
+            rook_from,
+            rook_to: Position::new(rook_x, y),
+        }
+    }
+
+    pub fn is_king_side(&self) -> bool {
+        self.rook_from.x > self.king_from.x
+    }
+}
@@ -13,6 +13,7 @@ impl fmt::Debug for Colour {
 }
 
 impl Colour {
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_symbol(&self) -> char {
         match self {
             Colour::White => 'W',
@@ -24,6 +25,15 @@ impl Colour {
         if self == &Colour::White { Colour::Black } else { Colour::White }
     }
 
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_index(&self) -> usize {
+        match self {
+            Colour::White => 0,
+            Colour::Black => 1,
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_num(&self) -> isize {
         match self {
             Colour::White => 1,
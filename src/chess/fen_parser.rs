@@ -1,93 +1,429 @@
+use super::bitboard;
 use super::board::Board;
 use super::colour::Colour;
 use super::position::Position;
 use super::piece::Piece;
+use super::piece_kind::PieceKind;
+use super::three_check::CHECKS_TO_WIN;
+use super::variant::Variant;
 use super::SIZE;
+use std::fmt;
+
+const FIELD_NAMES: [&str; 6] = ["piece placement", "side to move", "castling", "en passant", "halfmove clock", "fullmove number"];
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    MissingField(&'static str),
+    RankCount(usize),
+    RankLength{rank: i8, length: usize},
+    UnknownPiece(char),
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    HalfmoveClock(String),
+    MoveNumber(String),
+    Pocket(String),
+    Checks(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::RankCount(count) => write!(f, "expected 8 ranks but found {}", count),
+            FenError::RankLength{rank, length} => write!(f, "rank {} has {} squares instead of 8", rank + 1, length),
+            FenError::UnknownPiece(c) => write!(f, "unknown piece {:?}", c),
+            FenError::SideToMove(field) => write!(f, "invalid side to move {:?}", field),
+            FenError::Castling(field) => write!(f, "invalid castling rights {:?}", field),
+            FenError::EnPassant(field) => write!(f, "invalid en passant square {:?}", field),
+            FenError::HalfmoveClock(field) => write!(f, "invalid halfmove clock {:?}", field),
+            FenError::MoveNumber(field) => write!(f, "invalid fullmove number {:?}", field),
+            FenError::Pocket(field) => write!(f, "invalid pocket {:?}", field),
+            FenError::Checks(field) => write!(f, "invalid check counts {:?}", field),
+        }
+    }
+}
 
 struct FenParser {
     board: Board,
 }
 
 impl FenParser {
-    fn is_digit(c: char) -> bool {
-        48 <= c as usize && c as usize <= 58
-    }
-
-    fn parse_piece_positions(&mut self, field: &str) {
-        let mut y = SIZE;
-        for row in field.split("/") {
-            let mut x = 0i8;
-            y -= 1;
+    fn parse_piece_positions(&mut self, field: &str) -> Result<(), FenError> {
+        let crazyhouse = self.board.variant == Variant::Crazyhouse;
+        let (field, pocket) = match field.find('[') {
+            Some(start) if field.ends_with(']') => (&field[..start], &field[start + 1..field.len() - 1]),
+            _ => (field, ""),
+        };
+        let mut rows: Vec<&str> = field.split('/').collect();
+        // Crazyhouse pockets may also be written as a ninth rank
+        if crazyhouse && pocket.is_empty() && rows.len() == SIZE as usize + 1 {
+            self.parse_pocket(rows.pop().unwrap())?;
+        } else {
+            self.parse_pocket(pocket)?;
+        }
+        if rows.len() != SIZE as usize {
+            return Err(FenError::RankCount(rows.len()));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            let y = SIZE - 1 - i as i8;
+            let mut x = 0usize;
             for c in row.chars() {
-                if FenParser::is_digit(c) {
-                    x += c as i8 - 48;
+                if c == '~' && crazyhouse && x > 0 && x <= SIZE as usize {
+                    let index = Position::new(x as i8 - 1, y).get_index().unwrap();
+                    self.board.promoted |= bitboard::square(index);
+                } else if let Some(empty) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                    x += empty as usize;
                 } else {
-                    self.board.place_piece(
-                        x,
-                        y,
-                        Piece::from_symbol(c).unwrap()
-                    );
+                    let piece = Some(c).filter(|c| c.is_ascii_alphabetic()).and_then(Piece::from_symbol).ok_or(FenError::UnknownPiece(c))?;
+                    if x < SIZE as usize {
+                        self.board.place_piece(x as i8, y, piece);
+                    }
                     x += 1;
                 }
             }
+            if x != SIZE as usize {
+                return Err(FenError::RankLength{rank: y, length: x});
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_pocket(&mut self, pocket: &str) -> Result<(), FenError> {
+        if !pocket.is_empty() && self.board.variant != Variant::Crazyhouse {
+            return Err(FenError::Pocket(String::from(pocket)));
+        }
+        for c in pocket.chars() {
+            let piece = Some(c).filter(|c| c.is_ascii_alphabetic()).and_then(Piece::from_symbol)
+                .filter(|piece| piece.kind != PieceKind::King)
+                .ok_or_else(|| FenError::Pocket(String::from(pocket)))?;
+            self.board.pockets[piece.colour.to_index()][piece.kind.to_index()] += 1;
         }
+        Ok(())
     }
 
-    fn parse_active_move(&mut self, field: &str) {
-        match field.chars().next() {
-            Some('w') => self.board.turn = Colour::White,
-            Some('b') => self.board.turn = Colour::Black,
-            _ => (),
+    // Checks given as in +1+0, or checks remaining as in 2+3
+    fn parse_checks(&mut self, field: &str, remaining: bool) -> Result<(), FenError> {
+        let error = || FenError::Checks(String::from(field));
+        if self.board.variant != Variant::ThreeCheck {
+            return Err(error());
+        }
+        let counts = if remaining { field } else { field.strip_prefix('+').ok_or_else(error)? };
+        let (white, black) = counts.split_once('+').ok_or_else(error)?;
+        for (colour, count) in [white, black].iter().enumerate() {
+            let count = Some(count).filter(|count| count.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|count| count.parse::<u8>().ok())
+                .filter(|count| *count <= CHECKS_TO_WIN)
+                .ok_or_else(error)?;
+            self.board.checks[colour] = if remaining { CHECKS_TO_WIN - count } else { count };
+        }
+        Ok(())
+    }
+
+    fn parse_active_move(&mut self, field: &str) -> Result<(), FenError> {
+        self.board.turn = match field {
+            "w" => Colour::White,
+            "b" => Colour::Black,
+            _ => return Err(FenError::SideToMove(String::from(field))),
         };
+        Ok(())
     }
 
-    fn parse_castling(&mut self, field: &str) {
+    fn parse_castling(&mut self, field: &str) -> Result<(), FenError> {
+        if field == "-" {
+            return Ok(());
+        }
+        let error = || FenError::Castling(String::from(field));
         for c in field.chars() {
-            match c {
-                'K' => self.board.castle_white_king_side = true,
-                'Q' => self.board.castle_white_queen_side = true,
-                'k' => self.board.castle_black_king_side = true,
-                'q' => self.board.castle_black_queen_side = true,
-                _ => (),
-            };
+            let colour = if c.is_ascii_uppercase() { Colour::White } else { Colour::Black };
+            let y = Board::home_rank(colour);
+            let is_rook = |x: i8| self.board.get(Position::new(x, y)) == Some(Piece::new(PieceKind::Rook, colour));
+            let king_x = (0..SIZE).find(|x| self.board.get(Position::new(*x, y)) == Some(Piece::new(PieceKind::King, colour))).ok_or_else(error)?;
+            // X-FEN K and Q name the outermost rook on that side, Shredder-FEN names the rook file
+            let rook_x = match c.to_ascii_lowercase() {
+                'k' => (king_x + 1..SIZE).rev().find(|x| is_rook(*x)),
+                'q' => (0..king_x).find(|x| is_rook(*x)),
+                file @ 'a'..='h' => Some(file as i8 - 'a' as i8).filter(|x| is_rook(*x)),
+                _ => None,
+            }.ok_or_else(error)?;
+            let right = Board::castle_right(colour, rook_x > king_x);
+            if self.board.can_castle(right) {
+                return Err(error());
+            }
+            self.board.set_can_castle(right, true);
+            self.board.castle_rook_files[right] = rook_x;
+            let named_by_file = !"kq".contains(c.to_ascii_lowercase());
+            if named_by_file || king_x != 4 || (rook_x != 0 && rook_x != SIZE - 1) {
+                self.board.chess960 = true;
+            }
         }
+        Ok(())
     }
 
-    fn parse_en_passant(&mut self, field: &str) {
-        self.board.en_passant = Position::from_symbol(field);
+    // The square must be behind a pawn that has just moved two squares, so its rank depends on the side to move
+    fn parse_en_passant(&mut self, field: &str) -> Result<(), FenError> {
+        if field == "-" {
+            return Ok(());
+        }
+        let rank = match self.board.turn {
+            Colour::White => 5,
+            Colour::Black => 2,
+        };
+        self.board.en_passant = Some(Position::from_symbol(field)
+            .filter(|pos| field.len() == 2 && !pos.is_off_board() && pos.y == rank)
+            .ok_or_else(|| FenError::EnPassant(String::from(field)))?);
+        Ok(())
     }
 
-    fn parse_halfmove_clock(&mut self, field: &str) {
-        let move_count: Option<usize> = field.parse::<usize>().ok();
-        move_count.and_then(|x| {
-            self.board.half_move_number = x;
-            Some(x)
-        });
+    fn parse_halfmove_clock(&mut self, field: &str) -> Result<(), FenError> {
+        self.board.half_move_number = field.parse::<usize>().map_err(|_| FenError::HalfmoveClock(String::from(field)))?;
+        Ok(())
     }
 
-    fn parse_turn_count(&mut self, field: &str) {
-        let move_count: Option<usize> = field.parse::<usize>().ok();
-        move_count.and_then(|x| {
-            self.board.move_number = x;
-            Some(x)
-        });
+    fn parse_turn_count(&mut self, field: &str) -> Result<(), FenError> {
+        self.board.move_number = field.parse::<usize>().ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| FenError::MoveNumber(String::from(field)))?;
+        Ok(())
     }
 }
 
-pub fn parse(fen: &str) -> Result<Board, ()> {
-    if fen == "startpos" {
-        return parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    }
+pub fn parse(fen: &str) -> Result<Board, FenError> {
+    parse_variant(fen, Variant::Standard)
+}
+
+// Parses a position to be played under a variant's rules, startpos is the variant's starting position
+pub fn parse_variant(fen: &str, variant: Variant) -> Result<Board, FenError> {
+    let fen = if fen == "startpos" { variant.starting_fen() } else { fen };
     let mut fen_parser = FenParser{
         board: Board::new()
     };
-    let fields: Vec<&str> = fen.split(" ").collect();
-    fen_parser.parse_piece_positions(fields.get(0).ok_or_else(|| ())?);
-    fen_parser.parse_active_move(fields.get(1).ok_or_else(|| ())?);
-    fen_parser.parse_castling(fields.get(2).ok_or_else(|| ())?);
-    fen_parser.parse_en_passant(fields.get(3).ok_or_else(|| ())?);
-    fen_parser.parse_halfmove_clock(fields.get(4).ok_or_else(|| ())?);
-    fen_parser.parse_turn_count(fields.get(5).ok_or_else(|| ())?);
+    fen_parser.board.variant = variant;
+    fen_parser.board.chess960 = variant == Variant::Chess960;
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    // Older three-check FENs give the checks remaining after the en passant square
+    if fields.get(4).is_some_and(|field| field.contains('+')) {
+        let remaining = fields.remove(4);
+        fen_parser.parse_checks(remaining, true)?;
+    }
+    let field = |i: usize| fields.get(i).copied().ok_or(FenError::MissingField(FIELD_NAMES[i]));
+    fen_parser.parse_piece_positions(field(0)?)?;
+    fen_parser.parse_active_move(field(1)?)?;
+    fen_parser.parse_castling(field(2)?)?;
+    fen_parser.parse_en_passant(field(3)?)?;
+    fen_parser.parse_halfmove_clock(field(4)?)?;
+    fen_parser.parse_turn_count(field(5)?)?;
+    if let Some(checks) = fields.get(6) {
+        fen_parser.parse_checks(checks, false)?;
+    }
+    fen_parser.board.refresh_hash();
 
     Ok(fen_parser.board)
-}
\ No newline at end of file
+}
+
+impl Board {
+    // Chess960 castling rights are written as X-FEN, using the rook file only when K or Q would name a different rook
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    // Writes every castling right as its rook file as in HAha
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    fn castling_field(&self, shredder: bool) -> String {
+        let mut castling = String::default();
+        for (colour, symbols) in [(Colour::White, "KQ"), (Colour::Black, "kq")].iter() {
+            let y = Board::home_rank(*colour);
+            for (king_side, symbol) in [true, false].iter().zip(symbols.chars()) {
+                let right = Board::castle_right(*colour, *king_side);
+                if !self.can_castle(right) {
+                    continue;
+                }
+                let rook_x = self.castle_rook_files[right];
+                let outer = if *king_side { rook_x + 1..SIZE } else { 0..rook_x };
+                let outermost = !outer.into_iter().any(|x| self.get(Position::new(x, y)) == Some(Piece::new(PieceKind::Rook, *colour)));
+                if shredder || (self.chess960 && !outermost) {
+                    let file = (b'a' + rook_x as u8) as char;
+                    castling.push(if symbol.is_ascii_uppercase() { file.to_ascii_uppercase() } else { file });
+                } else {
+                    castling.push(symbol);
+                }
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        castling
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut rows = Vec::with_capacity(SIZE as usize);
+        for y in (0..SIZE).rev() {
+            let mut row = String::default();
+            let mut empty = 0;
+            for x in 0..SIZE {
+                match self.get(Position::new(x, y)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        row.push(piece.to_symbol());
+                        if self.promoted & bitboard::square(Position::new(x, y).get_index().unwrap()) != bitboard::EMPTY {
+                            row.push('~');
+                        }
+                    },
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            rows.push(row);
+        }
+        let mut placement = rows.join("/");
+        if self.variant == Variant::Crazyhouse {
+            placement.push('[');
+            for colour in [Colour::White, Colour::Black].iter() {
+                for kind in PieceKind::ALL.iter().skip(1) {
+                    let symbol = Piece::new(*kind, *colour).to_symbol();
+                    placement.extend((0..self.pockets[colour.to_index()][kind.to_index()]).map(|_| symbol));
+                }
+            }
+            placement.push(']');
+        }
+
+        let fen = format!(
+            "{} {} {} {} {} {}",
+            placement,
+            self.turn.to_symbol().to_ascii_lowercase(),
+            self.castling_field(shredder),
+            self.en_passant.map_or(String::from("-"), |pos| pos.to_symbol()),
+            self.half_move_number,
+            self.move_number,
+        );
+        match self.variant {
+            Variant::ThreeCheck => format!("{} +{}+{}", fen, self.checks[Colour::White.to_index()], self.checks[Colour::Black.to_index()]),
+            _ => fen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::r#move::Move;
+    use crate::chess::variant::STANDARD_FEN;
+
+    const FENS: [&str; 10] = [
+        STANDARD_FEN,
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/8/8/8/8/8/8/k6K b - - 99 213",
+    ];
+
+    #[test]
+    fn round_trips_fens() {
+        for fen in FENS.iter() {
+            let board = parse(fen).unwrap();
+            assert_eq!(board.to_fen(), *fen);
+            assert!(parse(&board.to_fen()).unwrap() == board);
+        }
+    }
+
+    #[test]
+    fn round_trips_played_positions() {
+        let mut board = parse("startpos").unwrap();
+        for symbol in ["e2e4", "c7c5", "g1f3", "d7d6", "f1b5", "c8d7", "e1g1"].iter() {
+            board.play_move(Move::from_symbol(symbol).unwrap());
+            assert!(parse(&board.to_fen()).unwrap() == board, "{}", board.to_fen());
+        }
+    }
+
+    #[test]
+    fn reads_and_writes_chess960_castling() {
+        let board = parse("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap();
+        assert!(board.chess960);
+        assert_eq!(board.castle_rook_files, [7, 5, 7, 5]);
+        assert_eq!(board.to_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        assert_eq!(board.to_shredder_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert!(parse(&board.to_fen()).unwrap() == board);
+
+        // The e file rook is further out so the f file rook has to be named
+        let fen = "4rrk1/pbbp2p1/1ppnp3/3n1pqp/3N1PQP/1PPNP3/PBBP2P1/4RRK1 w Ff - 0 1";
+        let board = parse(fen).unwrap();
+        assert_eq!(board.castle_rook_files[0..2], [7, 5]);
+        assert_eq!(board.to_fen(), fen);
+
+        let standard = parse(STANDARD_FEN).unwrap();
+        assert!(!standard.chess960);
+        assert_eq!(standard.to_shredder_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+        assert!(parse(&standard.to_shredder_fen()).unwrap().chess960);
+    }
+
+    #[test]
+    fn reports_bad_placement() {
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(), Some(FenError::RankCount(7)));
+        assert_eq!(parse("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(), Some(FenError::RankLength{rank: 6, length: 7}));
+        assert_eq!(parse("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(), Some(FenError::UnknownPiece('9')));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1").err(), Some(FenError::RankLength{rank: 0, length: 9}));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1").err(), Some(FenError::UnknownPiece('X')));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN\t w KQkq - 0 1").err(), Some(FenError::RankLength{rank: 0, length: 7}));
+    }
+
+    #[test]
+    fn reads_and_writes_crazyhouse_pockets() {
+        let crazyhouse = |fen: &str| parse_variant(fen, Variant::Crazyhouse);
+        let board = crazyhouse("4k3/1Q~6/8/8/4b3/8/Kpp5/8/Qnn b - - 0 1").unwrap();
+        assert_eq!(board.pockets[Colour::White.to_index()][PieceKind::Queen.to_index()], 1);
+        assert_eq!(board.pockets[Colour::Black.to_index()][PieceKind::Knight.to_index()], 2);
+        assert_eq!(board.to_fen(), "4k3/1Q~6/8/8/4b3/8/Kpp5/8[Qnn] b - - 0 1");
+        assert!(crazyhouse(&board.to_fen()).unwrap() == board);
+        assert_eq!(crazyhouse("startpos").unwrap().to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1");
+        assert_eq!(crazyhouse("4k3/8/8/8/8/8/8/4K3[Kq] w - - 0 1").err(), Some(FenError::Pocket(String::from("Kq"))));
+        assert_eq!(parse("4k3/8/8/8/8/8/8/4K3[q] w - - 0 1").err(), Some(FenError::Pocket(String::from("q"))));
+    }
+
+    #[test]
+    fn reads_and_writes_three_check_counts() {
+        let three_check = |fen: &str| parse_variant(fen, Variant::ThreeCheck);
+        let board = three_check("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1").unwrap();
+        assert_eq!(board.checks, [2, 1]);
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1");
+        assert!(three_check(&board.to_fen()).unwrap() == board);
+        assert_eq!(three_check("4k3/8/8/8/8/8/8/4K3 w - - 1+2 0 1").unwrap().to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1");
+        assert_eq!(three_check("startpos").unwrap().checks, [0, 0]);
+        assert_eq!(three_check("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +4+0").err(), Some(FenError::Checks(String::from("+4+0"))));
+        assert_eq!(three_check("4k3/8/8/8/8/8/8/4K3 w - - 0 1 1+0").err(), Some(FenError::Checks(String::from("1+0"))));
+        assert_eq!(parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +1+0").err(), Some(FenError::Checks(String::from("+1+0"))));
+    }
+
+    #[test]
+    fn reports_bad_fields() {
+        let with = |fields: &str| parse(&format!("4k3/8/8/8/8/8/8/4K3 {}", fields)).err();
+        assert_eq!(with("x - - 0 1"), Some(FenError::SideToMove(String::from("x"))));
+        assert_eq!(with("w KX - 0 1"), Some(FenError::Castling(String::from("KX"))));
+        assert_eq!(with("w KK - 0 1"), Some(FenError::Castling(String::from("KK"))));
+        assert_eq!(with("w K - 0 1"), Some(FenError::Castling(String::from("K"))));
+        assert_eq!(parse("4k3/8/8/8/8/8/8/R3K2R w KHA - 0 1").err(), Some(FenError::Castling(String::from("KHA"))));
+        assert_eq!(parse("4k3/8/8/8/8/8/8/R3K2R w B - 0 1").err(), Some(FenError::Castling(String::from("B"))));
+        assert_eq!(with("w - e4 0 1"), Some(FenError::EnPassant(String::from("e4"))));
+        assert_eq!(with("w - e3 0 1"), Some(FenError::EnPassant(String::from("e3"))));
+        assert_eq!(with("w - e66 0 1"), Some(FenError::EnPassant(String::from("e66"))));
+        assert_eq!(with("w - - x 1"), Some(FenError::HalfmoveClock(String::from("x"))));
+        assert_eq!(with("w - - 0 -1"), Some(FenError::MoveNumber(String::from("-1"))));
+        assert_eq!(with("w - - 0 0"), Some(FenError::MoveNumber(String::from("0"))));
+        assert_eq!(with("w - -"), Some(FenError::MissingField("halfmove clock")));
+        assert!(with("w - e6 0 1").is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use super::board::Board;
+use super::game_status::GameStatus;
+use super::r#move::Move;
+
+// A game from its initial position, remembering every position so repetitions can be recognised
+#[derive(Clone)]
+pub struct GameRecord {
+    board: Board,
+    // Hashes of the positions before the current one, oldest first
+    history: Vec<u64>,
+}
+
+impl GameRecord {
+    pub fn new(initial_board: Board) -> Self {
+        Self {
+            board: initial_board,
+            history: Vec::default(),
+        }
+    }
+
+    // Replays space separated moves such as "e2e4 e7e5", None if any of them cannot be played
+    pub fn from_moves(initial_board: Board, moves: &str) -> Option<Self> {
+        let mut record = Self::new(initial_board);
+        for symbol in moves.split(' ').filter(|symbol| !symbol.is_empty()) {
+            if !record.play_move(Move::from_symbol(symbol)?) {
+                return None;
+            }
+        }
+        Some(record)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
+
+    pub fn play_move(&mut self, m: Move) -> bool {
+        let hash = self.board.hash();
+        if !self.board.play_move(m) {
+            return false;
+        }
+        self.history.push(hash);
+        true
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.board.status(&self.history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::variant::Variant;
+
+    fn status(fen: &str, moves: &str) -> GameStatus {
+        GameRecord::from_moves(fen_parser::parse(fen).unwrap(), moves).unwrap().status()
+    }
+
+    #[test]
+    fn detects_mate_and_stalemate() {
+        assert_eq!(status("startpos", "f2f3 e7e5 g2g4 d8h4"), GameStatus::Checkmate);
+        assert_eq!(status("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", ""), GameStatus::Stalemate);
+        assert_eq!(status("startpos", "e2e4"), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn detects_threefold_repetition() {
+        let shuffle = "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1";
+        assert_eq!(status("startpos", shuffle), GameStatus::Ongoing);
+        assert_eq!(status("startpos", &format!("{} f6g8", shuffle)), GameStatus::Repetition);
+        // Positions from before the pawn move cannot come back
+        assert_eq!(status("startpos", "g1f3 g8f6 f3g1 f6g8 e2e3 g8f6 g1f3 f6g8 f3g1"), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn detects_fifty_moves() {
+        assert_eq!(status("4k3/8/8/8/8/8/8/R3K3 w - - 99 80", ""), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/R3K3 w - - 99 80", "a1a2"), GameStatus::FiftyMove);
+        assert_eq!(status("4k3/8/8/8/8/8/4r3/4K3 w - - 99 80", "e1e2"), GameStatus::InsufficientMaterial);
+        // Mate on the hundredth half move still counts as mate
+        assert_eq!(status("7k/8/6K1/8/8/8/8/R7 w - - 99 80", "a1a8"), GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn detects_insufficient_material() {
+        assert_eq!(status("4k3/8/8/8/8/8/8/4K3 w - - 0 1", ""), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4k3/8/8/8/8/8/8/3NK3 w - - 0 1", ""), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", ""), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", ""), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1", ""), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/3PK3 w - - 0 1", ""), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn crazyhouse_only_draws_by_repetition() {
+        let crazyhouse = |fen: &str, moves: &str| GameRecord::from_moves(fen_parser::parse_variant(fen, Variant::Crazyhouse).unwrap(), moves).unwrap().status();
+        assert_eq!(crazyhouse("4k3/8/8/8/8/8/8/4K3[] w - - 0 1", ""), GameStatus::Ongoing);
+        assert_eq!(crazyhouse("4k3/8/8/8/8/8/8/R3K3[] w - - 99 80", "a1a2"), GameStatus::Ongoing);
+        assert_eq!(crazyhouse("startpos", "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8"), GameStatus::Repetition);
+    }
+
+    #[test]
+    fn keeps_clocks() {
+        let record = GameRecord::from_moves(fen_parser::parse("startpos").unwrap(), "e2e4 e7e5 g1f3 b8c6 f3e5").unwrap();
+        assert_eq!(record.board().half_move_number, 0);
+        assert_eq!(record.board().move_number, 3);
+        let record = GameRecord::from_moves(fen_parser::parse("startpos").unwrap(), "g1f3 g8f6 f3g1").unwrap();
+        assert_eq!(record.board().half_move_number, 3);
+        assert_eq!(record.board().move_number, 2);
+        assert_eq!(record.board().to_fen(), "rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 3 2");
+    }
+}
@@ -0,0 +1,90 @@
+use super::bitboard::{EMPTY, LIGHT_SQUARES};
+use super::board::Board;
+use super::variant::Variant;
+use super::piece_kind::PieceKind;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    FiftyMove,
+    Repetition,
+    InsufficientMaterial,
+    // The side to move has lost by a rule of the variant, such as its king exploding in atomic
+    VariantLoss,
+    // The side to move has won by a rule of the variant, such as having no pieces left in antichess
+    VariantWin,
+    // Drawn by a rule of the variant, such as both kings finishing the race in racing kings
+    VariantDraw,
+}
+
+
+impl Board {
+    // The history holds the hashes of the earlier positions of the game, oldest first
+    pub fn status(&self, history: &[u64]) -> GameStatus {
+        if let Some(status) = self.variant_status() {
+            status
+        } else if self.possible_moves().is_empty() {
+            if self.is_check(self.turn) {
+                GameStatus::Checkmate
+            } else {
+                GameStatus::Stalemate
+            }
+        } else {
+            self.draw_by_rule(history).unwrap_or(GameStatus::Ongoing)
+        }
+    }
+
+    // Draws that do not depend on the legal moves, checkmate on the move that completes them still wins
+    pub fn draw_by_rule(&self, history: &[u64]) -> Option<GameStatus> {
+        // Pieces in hand can always come back in crazyhouse, so only repetition draws
+        let insufficient = match self.variant {
+            Variant::Standard | Variant::Chess960 => self.is_insufficient_material(),
+            Variant::Crazyhouse => false,
+            Variant::Atomic => self.is_atomic_insufficient_material(),
+            Variant::Antichess => self.is_antichess_insufficient_material(),
+            // Even a lone king can walk to the hill
+            Variant::KingOfTheHill => false,
+            // Any piece besides the king can give check
+            Variant::ThreeCheck => self.occupied() == self.pieces(PieceKind::King),
+            // Even a lone king can win the race
+            Variant::RacingKings => false,
+            // The horde can only run out of mating material in ways too rare to be worth spotting
+            Variant::Horde => false,
+        };
+        if insufficient {
+            Some(GameStatus::InsufficientMaterial)
+        } else if self.variant != Variant::Crazyhouse && self.half_move_number >= 100 {
+            Some(GameStatus::FiftyMove)
+        } else if self.repetitions(history) >= 3 {
+            Some(GameStatus::Repetition)
+        } else {
+            None
+        }
+    }
+
+    // How many times the current position has occurred, only positions since the last capture or pawn move can match
+    pub fn repetitions(&self, history: &[u64]) -> usize {
+        let window = self.half_move_number.min(history.len());
+        1 + history[history.len() - window..].iter().rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|hash| **hash == self.hash())
+            .count()
+    }
+
+    // Neither side can mate with any sequence of moves: bare kings, a single minor piece, or bishops all on one square colour
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = [PieceKind::Pawn, PieceKind::Rook, PieceKind::Queen].iter().any(|kind| self.pieces(*kind) != EMPTY);
+        if heavy {
+            return false;
+        }
+        let knights = self.pieces(PieceKind::Knight);
+        let bishops = self.pieces(PieceKind::Bishop);
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+        knights == EMPTY && (bishops & LIGHT_SQUARES == EMPTY || bishops & !LIGHT_SQUARES == EMPTY)
+    }
+}
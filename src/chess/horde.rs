@@ -0,0 +1,46 @@
+use super::bitboard::EMPTY;
+use super::board::Board;
+use super::colour::Colour;
+use super::game_status::GameStatus;
+
+impl Board {
+    // White has no king and only loses once every piece of the horde is captured, black still loses by checkmate
+    pub fn horde_status(&self) -> Option<GameStatus> {
+        if self.occupied_by(Colour::White) != EMPTY {
+            None
+        } else if self.turn == Colour::White {
+            Some(GameStatus::VariantLoss)
+        } else {
+            Some(GameStatus::VariantWin)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::r#move::Move;
+    use crate::chess::variant::Variant;
+
+    fn horde(fen: &str) -> Board {
+        fen_parser::parse_variant(fen, Variant::Horde).unwrap()
+    }
+
+    #[test]
+    fn first_rank_pawns_step_twice_without_en_passant() {
+        let mut board = horde("4k3/8/8/8/8/1p6/8/P7 w - - 0 1");
+        let moves: Vec<String> = board.possible_moves().iter().map(|m| m.to_symbol()).collect();
+        assert_eq!(moves, vec!["a1a2", "a1a3"]);
+        assert!(board.play_move(Move::from_symbol("a1a3").unwrap()));
+        assert_eq!(board.en_passant, None);
+    }
+
+    #[test]
+    fn capturing_the_whole_horde_wins() {
+        assert_eq!(horde("4k3/8/8/8/8/8/8/8 w - - 0 1").status(&[]), GameStatus::VariantLoss);
+        assert_eq!(horde("4k3/8/8/8/8/8/8/8 b - - 0 1").status(&[]), GameStatus::VariantWin);
+        assert_eq!(horde("startpos").status(&[]), GameStatus::Ongoing);
+        assert_eq!(horde("k7/P7/1PP5/8/8/8/8/8 b - - 0 1").status(&[]), GameStatus::Stalemate);
+    }
+}
@@ -0,0 +1,65 @@
+use super::bitboard::{Bitboard, BitboardIter, EMPTY};
+use super::board::Board;
+use super::colour::Colour;
+use super::game_status::GameStatus;
+use super::piece_kind::PieceKind;
+use super::position::Position;
+
+// d4, e4, d5 and e5
+pub const HILL: Bitboard = 0x0000_0018_1800_0000;
+
+impl Board {
+    // A king reaching the hill wins straight away
+    pub fn king_of_the_hill_status(&self) -> Option<GameStatus> {
+        if self.pieces_of(PieceKind::King, self.turn.opposite()) & HILL != EMPTY {
+            Some(GameStatus::VariantLoss)
+        } else if self.pieces_of(PieceKind::King, self.turn) & HILL != EMPTY {
+            Some(GameStatus::VariantWin)
+        } else {
+            None
+        }
+    }
+
+    // King steps to the nearest hill square, zero on the hill
+    pub fn hill_distance(&self, colour: Colour) -> Option<i8> {
+        BitboardIter(self.pieces_of(PieceKind::King, colour)).next().map(|index| {
+            let pos = Position::from_index(index);
+            let off = |v: i8| (3 - v).max(v - 4).max(0);
+            off(pos.x).max(off(pos.y))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::r#move::Move;
+    use crate::chess::variant::Variant;
+
+    fn king_of_the_hill(fen: &str) -> Board {
+        fen_parser::parse_variant(fen, Variant::KingOfTheHill).unwrap()
+    }
+
+    #[test]
+    fn reaching_the_hill_wins() {
+        let mut board = king_of_the_hill("4k3/8/8/8/8/4K3/8/8 w - - 0 1");
+        assert_eq!(board.status(&[]), GameStatus::Ongoing);
+        assert!(board.play_move(Move::from_symbol("e3d4").unwrap()));
+        assert_eq!(board.status(&[]), GameStatus::VariantLoss);
+        assert!(board.possible_moves().is_empty());
+    }
+
+    #[test]
+    fn lone_kings_keep_playing() {
+        assert_eq!(king_of_the_hill("4k3/8/8/8/8/8/8/4K3 w - - 0 1").status(&[]), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn measures_distance_to_the_hill() {
+        let board = king_of_the_hill("k7/8/8/8/4K3/8/8/8 w - - 0 1");
+        assert_eq!(board.hill_distance(Colour::White), Some(0));
+        assert_eq!(board.hill_distance(Colour::Black), Some(3));
+        assert_eq!(king_of_the_hill("8/8/8/8/8/8/8/8 w - - 0 1").hill_distance(Colour::White), None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod board;
+pub mod bitboard;
 
 pub mod colour;
 pub mod piece_kind;
@@ -7,6 +8,21 @@ pub mod position;
 pub mod r#move;
 pub mod fen_parser;
 pub mod position_iter;
+pub mod zobrist;
+pub mod undo;
+pub mod castling;
+pub mod perft;
+pub mod san;
+pub mod pgn;
+pub mod game_status;
+pub mod game_record;
+pub mod variant;
+pub mod atomic;
+pub mod antichess;
+pub mod king_of_the_hill;
+pub mod three_check;
+pub mod racing_kings;
+pub mod horde;
 
 pub const SIZE: i8 = 8;
 pub const SQUARE_SIZE: usize = (SIZE * SIZE) as usize;
\ No newline at end of file
@@ -2,11 +2,13 @@ use super::position::Position;
 use super::piece_kind::PieceKind;
 use std::fmt;
 
-#[derive(Copy, Clone)]
+// A drop places a piece from the pocket, its from square is the same as its to square
+#[derive(Copy, Clone, PartialEq)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
     pub promote: Option<PieceKind>,
+    pub drop: Option<PieceKind>,
 }
 
 impl fmt::Display for Move {
@@ -27,21 +29,79 @@ impl Move {
             from,
             to,
             promote: None,
+            drop: None,
         }
     }
 
+    pub fn new_drop(kind: PieceKind, to: Position) -> Self {
+        Self{
+            from: to,
+            to,
+            promote: None,
+            drop: Some(kind),
+        }
+    }
+
+    // Parses UCI notation such as e2e4 or e7e8q, the promotion letter must be lowercase q, r, b, n or k for antichess.
+    // Drops are written as the uppercase piece then @ and the square, as in N@f3
     pub fn from_symbol(symbol: &str) -> Option<Move> {
-        if symbol == "" || symbol.len() < 4 {
+        if !symbol.is_ascii() || (symbol.len() != 4 && symbol.len() != 5) {
             return None;
         }
+        if symbol.len() == 4 && &symbol[1..2] == "@" {
+            let kind = symbol.chars().next().filter(|c| "PNBRQ".contains(*c)).and_then(PieceKind::from_symbol)?;
+            return Some(Move::new_drop(kind, Position::from_symbol(&symbol[2..4]).filter(|pos| !pos.is_off_board())?));
+        }
+        let promote = match symbol.chars().nth(4) {
+            Some(c) if "qrbnk".contains(c) => Some(PieceKind::from_symbol(c.to_ascii_uppercase())?),
+            Some(_) => return None,
+            None => None,
+        };
         Some(Move{
-            from: Position::from_symbol(&symbol[0..2])?,
-            to: Position::from_symbol(&symbol[2..4])?,
-            promote: if symbol.len() >= 5 { PieceKind::from_symbol((symbol.chars().nth(4)? as u8 - 32) as char) } else { None },
+            from: Position::from_symbol(&symbol[0..2]).filter(|pos| !pos.is_off_board())?,
+            to: Position::from_symbol(&symbol[2..4]).filter(|pos| !pos.is_off_board())?,
+            promote,
+            drop: None,
         })
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_symbol(&self) -> String {
-        format!("{}{}", self.from.to_symbol(), self.to.to_symbol())
+        if let Some(kind) = self.drop {
+            return format!("{}@{}", kind.to_symbol(), self.to.to_symbol());
+        }
+        match self.promote {
+            Some(promote) => format!("{}{}{}", self.from.to_symbol(), self.to.to_symbol(), promote.to_symbol().to_ascii_lowercase()),
+            None => format!("{}{}", self.from.to_symbol(), self.to.to_symbol()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_include_promotion() {
+        let m = Move::from_symbol("e7e8n").unwrap();
+        assert!(m.promote == Some(PieceKind::Knight));
+        assert_eq!(m.to_symbol(), "e7e8n");
+        assert_eq!(Move::from_symbol("e2e4").unwrap().to_symbol(), "e2e4");
+        assert_eq!(Move::from_symbol("e7e8k").unwrap().promote, Some(PieceKind::King));
+    }
+
+    #[test]
+    fn symbols_include_drops() {
+        let m = Move::from_symbol("N@f3").unwrap();
+        assert!(m.drop == Some(PieceKind::Knight));
+        assert_eq!(m.to.to_symbol(), "f3");
+        assert_eq!(m.to_symbol(), "N@f3");
+    }
+
+    #[test]
+    fn rejects_bad_symbols() {
+        for symbol in ["e7e8p", "e7e8Q", "e7e8x", "e7e8qq", "e2e", "e2e9", "i2e4", "e2é4", "K@e4", "n@e4", "N@e9", "N@e4q"].iter() {
+            assert!(Move::from_symbol(symbol).is_none(), "{}", symbol);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+use super::board::Board;
+use super::r#move::Move;
+
+impl Board {
+    // Perft split by the first move, useful for finding which move a wrong count comes from
+    pub fn divide(&self, depth: usize) -> Vec<(Move, u64)> {
+        let mut board = *self;
+        board.possible_moves().into_iter().map(|m| {
+            let undo = board.make_move(m);
+            let nodes = board.perft_in_place(depth.saturating_sub(1));
+            board.unmake_move(undo);
+            (m, nodes)
+        }).collect()
+    }
+
+    fn perft_in_place(&mut self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.possible_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves.into_iter().map(|m| {
+            let undo = self.make_move(m);
+            let nodes = self.perft_in_place(depth - 1);
+            self.unmake_move(undo);
+            nodes
+        }).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::variant::Variant;
+
+    // Reference positions and counts from the chessprogramming wiki Perft Results page
+    const INITIAL: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+    // From Peter Ellis Jones' perft positions
+    const PROMOTIONS: &str = "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1";
+    // two-stepper-checkers from the tricky.perft suite of the shakmaty crate
+    const PROMOTION_CHECKERS: &str = "1q4k1/3r1Ppp/5NP1/pP6/8/1Q6/3B4/2K2R2 b - - 0 1";
+
+    // Counts the leaf nodes of the legal move tree, used to check move generation against known results
+    fn perft(board: &Board, depth: usize) -> u64 {
+        let mut board = *board;
+        board.perft_in_place(depth)
+    }
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        assert_variant_perft(fen, Variant::Standard, expected);
+    }
+
+    fn assert_variant_perft(fen: &str, variant: Variant, expected: &[u64]) {
+        let board = fen_parser::parse_variant(fen, variant).unwrap();
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&board, depth + 1), *nodes, "{} at depth {}", fen, depth + 1);
+        }
+    }
+
+    #[test]
+    fn perft_initial() {
+        assert_perft(INITIAL, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467]);
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(POSITION_5, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn perft_promotions() {
+        assert_perft(PROMOTIONS, &[24, 496, 9483, 182838]);
+        assert_perft(PROMOTION_CHECKERS, &[2, 98, 2826]);
+    }
+
+    #[test]
+    fn perft_chess960() {
+        assert_perft("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189]);
+        assert_perft("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002]);
+        assert_perft("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471]);
+        assert_perft("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", &[22, 593, 13440]);
+        assert_perft("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", &[29, 899, 26578]);
+        assert_perft("r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w KQkq - 0 1", &[23, 522, 12333]);
+        assert_perft("r1k2r1q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K2R1Q w KQkq - 0 1", &[28, 738, 20218]);
+        assert_perft("4rrk1/pbbp2p1/1ppnp3/3n1pqp/3N1PQP/1PPNP3/PBBP2P1/4RRK1 w Ff - 0 1", &[42, 1743, 71908]);
+    }
+
+    #[test]
+    fn perft_crazyhouse() {
+        assert_variant_perft("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", Variant::Crazyhouse, &[301, 75353]);
+        assert_variant_perft("2k5/8/8/8/8/8/8/4K3[Qn] w - - 0 1", Variant::Crazyhouse, &[67, 3083, 88634]);
+        assert_variant_perft("r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1", Variant::Crazyhouse, &[42, 1347, 58057]);
+        assert_variant_perft("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", Variant::Crazyhouse, &[20, 360, 5445]);
+    }
+
+    #[test]
+    fn perft_atomic() {
+        assert_variant_perft(INITIAL, Variant::Atomic, &[20, 400, 8902, 197326]);
+        assert_variant_perft("rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1", Variant::Atomic, &[40, 1238, 45237]);
+        assert_variant_perft("rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1", Variant::Atomic, &[28, 833, 23353]);
+        assert_variant_perft("8/8/8/8/8/8/2k5/rR4KR w KQ - 0 1", Variant::Atomic, &[18, 180, 4364, 61401]);
+        assert_variant_perft("r3k1rR/5K2/8/8/8/8/8/8 b kq - 0 1", Variant::Atomic, &[25, 282, 6753, 98729]);
+        assert_variant_perft("Rr2k1rR/3K4/3p4/8/8/8/7P/8 w kq - 0 1", Variant::Atomic, &[21, 465, 10631, 241478]);
+    }
+
+    #[test]
+    fn perft_antichess() {
+        assert_variant_perft("startpos", Variant::Antichess, &[20, 400, 8067, 153299]);
+        assert_variant_perft("8/1p6/8/8/8/8/P7/8 w - - 0 1", Variant::Antichess, &[2, 4, 4, 3, 1, 0]);
+        assert_variant_perft("8/2p5/8/8/8/8/P7/8 w - - 0 1", Variant::Antichess, &[2, 4, 4, 4, 4, 4, 4, 4, 12, 36, 312, 2557]);
+    }
+
+    #[test]
+    fn perft_three_check() {
+        assert_variant_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1", Variant::ThreeCheck, &[48, 2039, 97848]);
+        assert_variant_perft("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 1+1 0 1", Variant::ThreeCheck, &[26, 562, 13410]);
+    }
+
+    #[test]
+    fn perft_racing_kings() {
+        assert_variant_perft("startpos", Variant::RacingKings, &[21, 421, 11264]);
+        assert_variant_perft("4brn1/2K2k2/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings, &[6, 33, 178, 3151, 12981]);
+    }
+
+    #[test]
+    fn perft_horde() {
+        assert_variant_perft("startpos", Variant::Horde, &[8, 128, 1274, 23310]);
+        assert_variant_perft("4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1", Variant::Horde, &[30, 241, 6633, 56539]);
+        assert_variant_perft("k7/5p2/4p2P/3p2P1/2p2P2/1p2P2P/p2P2P1/2P2P2 w - - 0 1", Variant::Horde, &[13, 172, 2205, 33781]);
+    }
+
+    #[test]
+    fn every_promotion_is_generated() {
+        let board = fen_parser::parse(PROMOTIONS).unwrap();
+        let mut promotions: Vec<String> = board.possible_moves().into_iter()
+            .filter(|m| m.promote.is_some())
+            .map(|m| m.to_symbol())
+            .collect();
+        promotions.sort();
+        assert_eq!(promotions, vec![
+            "g2f1b", "g2f1n", "g2f1q", "g2f1r",
+            "g2g1b", "g2g1n", "g2g1q", "g2g1r",
+            "g2h1b", "g2h1n", "g2h1q", "g2h1r",
+        ]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_deep() {
+        assert_perft(INITIAL, &[20, 400, 8902, 197281, 4865609]);
+        assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624, 11030083]);
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333, 15833292]);
+        assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
+        assert_perft(PROMOTIONS, &[24, 496, 9483, 182838, 3605103]);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = fen_parser::parse(KIWIPETE).unwrap();
+        let divide = board.divide(2);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), perft(&board, 2));
+    }
+}
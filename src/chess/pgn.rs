@@ -0,0 +1,437 @@
+use super::board::Board;
+use super::colour::Colour;
+use super::fen_parser;
+use super::r#move::Move;
+use super::variant::Variant;
+use std::fmt;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const LINE_WIDTH: usize = 80;
+
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_board: Board,
+    pub moves: Vec<Move>,
+    // The comment following each mainline move, if any
+    pub comments: Vec<Option<String>>,
+    pub result: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    InvalidFen(String),
+    UnknownVariant(String),
+    IllegalMove{ply: usize, san: String},
+    Unterminated(&'static str),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidFen(fen) => write!(f, "invalid FEN tag {}", fen),
+            PgnError::UnknownVariant(name) => write!(f, "unknown variant {}", name),
+            PgnError::IllegalMove{ply, san} => write!(f, "illegal move {} at ply {}", san, ply),
+            PgnError::Unterminated(what) => write!(f, "unterminated {}", what),
+        }
+    }
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    // The board before the first move followed by the board after every mainline move
+    pub fn boards(&self) -> Vec<Board> {
+        let mut board = self.initial_board;
+        let mut boards = vec![board];
+        for m in self.moves.iter() {
+            board.play_move(*m);
+            boards.push(board);
+        }
+        boards
+    }
+
+    // Writes the game as PGN with the tags in the order they are stored and the movetext wrapped at 80 columns
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::default();
+        for (name, value) in self.tags.iter() {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::with_capacity(self.moves.len() * 2);
+        let mut board = self.initial_board;
+        let mut move_number = board.move_number;
+        let mut needs_number = true;
+        for (i, m) in self.moves.iter().enumerate() {
+            if board.turn == Colour::White {
+                tokens.push(format!("{}.", move_number));
+            } else if needs_number {
+                tokens.push(format!("{}...", move_number));
+            }
+            tokens.push(board.move_to_san(*m));
+            if board.turn == Colour::Black {
+                move_number += 1;
+            }
+            board.play_move(*m);
+            needs_number = false;
+            if let Some(Some(comment)) = self.comments.get(i) {
+                tokens.push(format!("{{{}}}", comment.replace('}', "")));
+                needs_number = true;
+            }
+        }
+        tokens.push(self.result.clone().unwrap_or_else(|| String::from("*")));
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+        pgn
+    }
+}
+
+// Iterates over the games in a PGN document, only the mainline of each game is kept
+pub struct PgnReader<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+pub fn parse(pgn: &str) -> PgnReader<'_> {
+    PgnReader{chars: pgn.chars().peekable()}
+}
+
+// A game as written in the document before the moves are checked
+#[derive(Default)]
+struct RawGame {
+    tags: Vec<(String, String)>,
+    sans: Vec<String>,
+    comments: Vec<Option<String>>,
+    result: Option<String>,
+}
+
+impl RawGame {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.sans.is_empty() && self.result.is_none()
+    }
+
+    fn into_game(self) -> Result<PgnGame, PgnError> {
+        let fen = self.tags.iter().find(|(tag, _)| tag == "FEN").map(|(_, fen)| fen.clone());
+        let result_tag = self.tags.iter().find(|(tag, _)| tag == "Result").map(|(_, result)| result.clone());
+        let result = self.result.or(result_tag);
+        let variant = match self.tags.iter().find(|(tag, _)| tag == "Variant") {
+            Some((_, name)) => Variant::from_name(name).ok_or_else(|| PgnError::UnknownVariant(name.clone()))?,
+            None => Variant::Standard,
+        };
+        let mut board = match fen {
+            Some(fen) => fen_parser::parse_variant(&fen, variant).map_err(|_| PgnError::InvalidFen(fen))?,
+            None => fen_parser::parse_variant("startpos", variant).unwrap(),
+        };
+        let initial_board = board;
+        let mut moves = Vec::with_capacity(self.sans.len());
+        for (ply, san) in self.sans.into_iter().enumerate() {
+            let m = board.parse_san(&san).ok_or(PgnError::IllegalMove{ply: ply + 1, san})?;
+            board.play_move(m);
+            moves.push(m);
+        }
+        Ok(PgnGame{tags: self.tags, initial_board, moves, comments: self.comments, result})
+    }
+}
+
+impl<'a> PgnReader<'a> {
+    fn skip_until(&mut self, end: char, what: &'static str) -> Result<(), PgnError> {
+        for c in self.chars.by_ref() {
+            if c == end {
+                return Ok(());
+            }
+        }
+        Err(PgnError::Unterminated(what))
+    }
+
+    fn read_tag(&mut self) -> Result<(String, String), PgnError> {
+        let mut name = String::default();
+        while let Some(c) = self.chars.next_if(|c| !c.is_whitespace() && *c != '"' && *c != ']') {
+            name.push(c);
+        }
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::default();
+        if self.chars.next_if_eq(&'"').is_some() {
+            loop {
+                match self.chars.next() {
+                    Some('\\') => value.extend(self.chars.next()),
+                    Some('"') => break,
+                    Some(c) => value.push(c),
+                    None => return Err(PgnError::Unterminated("tag")),
+                }
+            }
+        }
+        self.skip_until(']', "tag")?;
+        Ok((name, value))
+    }
+
+    // Skips a variation including any nested variations and comments inside it
+    fn skip_variation(&mut self) -> Result<(), PgnError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.chars.next() {
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                Some('{') => self.skip_until('}', "comment")?,
+                Some(';') => { self.skip_until('\n', "comment").ok(); },
+                Some(_) => (),
+                None => return Err(PgnError::Unterminated("variation")),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_comment(&mut self) -> Result<String, PgnError> {
+        let mut comment = String::default();
+        loop {
+            match self.chars.next() {
+                Some('}') => return Ok(comment),
+                Some(c) => comment.push(c),
+                None => return Err(PgnError::Unterminated("comment")),
+            }
+        }
+    }
+
+    fn read_token(&mut self) -> String {
+        let mut token = String::default();
+        while let Some(c) = self.chars.next_if(|c| !c.is_whitespace() && !"{}();[]$".contains(*c)) {
+            token.push(c);
+        }
+        token
+    }
+
+    fn read_game(&mut self) -> Result<RawGame, PgnError> {
+        let mut game = RawGame::default();
+        let mut line_start = true;
+        while let Some(&c) = self.chars.peek() {
+            if c == '[' && !game.sans.is_empty() {
+                break;
+            }
+            if c == '%' && line_start {
+                self.skip_until('\n', "escape").ok();
+                continue;
+            }
+            line_start = c == '\n';
+            match c {
+                '[' => {
+                    self.chars.next();
+                    let tag = self.read_tag()?;
+                    game.tags.push(tag);
+                },
+                '{' => {
+                    self.chars.next();
+                    let comment = self.read_comment()?;
+                    let comment = comment.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    // Comments before the first move have nowhere to go and are dropped
+                    if let Some(last) = game.comments.last_mut() {
+                        *last = match last.take() {
+                            Some(previous) => Some(format!("{} {}", previous, comment)),
+                            None => Some(comment),
+                        };
+                    }
+                },
+                ';' => {
+                    self.skip_until('\n', "comment").ok();
+                    line_start = true;
+                },
+                '(' => {
+                    self.chars.next();
+                    self.skip_variation()?;
+                },
+                '$' => {
+                    self.chars.next();
+                    while self.chars.next_if(|c| c.is_ascii_digit()).is_some() {}
+                },
+                c if c.is_whitespace() || c == ')' || c == '}' || c == ']' => {
+                    self.chars.next();
+                },
+                _ => {
+                    let token = self.read_token();
+                    if RESULTS.contains(&token.as_str()) {
+                        game.result = Some(token);
+                        break;
+                    }
+                    // Move numbers such as 12. or 12... come before the move, possibly without a space
+                    let san = match token.rfind('.') {
+                        Some(i) if token.starts_with(|c: char| c.is_ascii_digit()) => &token[i + 1..],
+                        _ => token.as_str(),
+                    };
+                    if !san.is_empty() {
+                        game.sans.push(String::from(san));
+                        game.comments.push(None);
+                    }
+                },
+            }
+        }
+        Ok(game)
+    }
+}
+
+impl<'a> Iterator for PgnReader<'a> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_game() {
+            Ok(game) if game.is_empty() => None,
+            Ok(game) => Some(game.into_game()),
+            Err(e) => {
+                // The rest of the document cannot be trusted after an unterminated token
+                while self.chars.next().is_some() {}
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::game_status::GameStatus;
+
+    const GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+[Event "Annotated"]
+[White "Player \"One\""]
+[Black "Two"]
+[Result "1-0"]
+
+1.e4 $1 e5 (1...c5 2.Nf3 (2.Nc3 {closed} Nc6) d6) 2.Qh5?! ; a comment
+Nc6 3.Bc4 Nf6?? 4.Qxf7# 1-0
+"#;
+
+    #[test]
+    fn reads_tags_and_mainline() {
+        let games: Vec<PgnGame> = parse(GAMES).map(|game| game.unwrap()).collect();
+        assert_eq!(games.len(), 2);
+        let game = &games[0];
+        assert_eq!(game.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(game.tag("Round"), Some("29"));
+        assert_eq!(game.moves.len(), 85);
+        assert_eq!(game.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(game.moves[8].to_symbol(), "e1g1");
+    }
+
+    #[test]
+    fn skips_comments_nags_and_variations() {
+        let games: Vec<PgnGame> = parse(GAMES).map(|game| game.unwrap()).collect();
+        let game = &games[1];
+        assert_eq!(game.tag("White"), Some("Player \"One\""));
+        let moves: Vec<String> = game.moves.iter().map(|m| m.to_symbol()).collect();
+        assert_eq!(moves, vec!["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"]);
+        assert_eq!(game.result.as_deref(), Some("1-0"));
+        assert_eq!(game.boards().last().unwrap().status(&[]), GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn honours_fen_tag() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n\n1... Kd7 2. e4 *";
+        let game = parse(pgn).next().unwrap().unwrap();
+        assert_eq!(game.moves.len(), 2);
+        assert_eq!(game.moves[0].to_symbol(), "e8d7");
+        assert_eq!(game.result.as_deref(), Some("*"));
+        assert_eq!(game.boards().len(), 3);
+    }
+
+    #[test]
+    fn reports_illegal_moves_and_continues() {
+        let pgn = "[Event \"Bad\"]\n\n1. e4 e4 *\n\n[Event \"Good\"]\n\n1. d4 *";
+        let games: Vec<Result<PgnGame, PgnError>> = parse(pgn).collect();
+        assert_eq!(games.len(), 2);
+        match &games[0] {
+            Err(PgnError::IllegalMove{ply, san}) => {
+                assert_eq!(*ply, 2);
+                assert_eq!(san, "e4");
+            },
+            _ => panic!("expected an illegal move"),
+        }
+        assert_eq!(games[1].as_ref().unwrap().moves.len(), 1);
+    }
+
+    #[test]
+    fn reads_variant_tag() {
+        let game = parse("[Variant \"Crazyhouse\"]\n\n1. e4 d5 2. exd5 Nf6 3. P@e5 *").next().unwrap().unwrap();
+        assert_eq!(game.initial_board.variant, Variant::Crazyhouse);
+        assert_eq!(game.moves[4].to_symbol(), "P@e5");
+        assert!(matches!(parse("[Variant \"Bughouse\"]\n\n1. e4 *").next(), Some(Err(PgnError::UnknownVariant(_)))));
+    }
+
+    #[test]
+    fn reports_unterminated_comment() {
+        let games: Vec<Result<PgnGame, PgnError>> = parse("1. e4 {never closed").collect();
+        assert!(matches!(games[..], [Err(PgnError::Unterminated("comment"))]));
+    }
+
+    #[test]
+    fn keeps_mainline_comments() {
+        let games: Vec<PgnGame> = parse(GAMES).map(|game| game.unwrap()).collect();
+        assert_eq!(games[0].comments[4].as_deref(), Some("This opening is called the Ruy Lopez."));
+        assert_eq!(games[0].comments.iter().filter(|c| c.is_some()).count(), 1);
+        assert!(games[1].comments.iter().all(|c| c.is_none()));
+    }
+
+    #[test]
+    fn writes_pgn_that_reads_back() {
+        let games: Vec<PgnGame> = parse(GAMES).map(|game| game.unwrap()).collect();
+        for game in games.iter() {
+            let pgn = game.to_pgn();
+            assert!(pgn.lines().all(|line| line.len() <= LINE_WIDTH), "{}", pgn);
+            let read = parse(&pgn).next().unwrap().unwrap();
+            assert_eq!(read.tags, game.tags);
+            assert!(read.moves == game.moves);
+            assert_eq!(read.comments, game.comments);
+            assert_eq!(read.result, game.result);
+        }
+    }
+
+    #[test]
+    fn writes_movetext() {
+        let mut game = parse("[Event \"?\"]\n\n1. e4 {[%eval 0.3]} e5 2. Nf3 *").next().unwrap().unwrap();
+        assert_eq!(game.to_pgn(), "[Event \"?\"]\n\n1. e4 {[%eval 0.3]} 1... e5 2. Nf3 *\n");
+        game.result = None;
+        game.tags.clear();
+        game.comments = vec![];
+        assert_eq!(game.to_pgn(), "\n1. e4 e5 2. Nf3 *\n");
+    }
+
+    #[test]
+    fn writes_numbers_from_fen() {
+        let game = parse("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 7\"]\n\n7... Kd7 8. e4 *").next().unwrap().unwrap();
+        assert!(game.to_pgn().ends_with("\n7... Kd7 8. e4 *\n"));
+    }
+
+    #[test]
+    fn starts_new_game_without_result() {
+        let pgn = "[Event \"One\"]\n1. e4\n[Event \"Two\"]\n1. d4 d5";
+        let games: Vec<PgnGame> = parse(pgn).map(|game| game.unwrap()).collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].result, None);
+        assert_eq!(games[1].moves.len(), 2);
+    }
+}
@@ -24,10 +24,18 @@ impl Piece {
         };
         Some(Piece{
             kind: PieceKind::from_symbol(piece_symbol)?
-            , colour: colour
+            , colour
         })
     }
 
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_symbol(&self) -> char {
+        match self.colour {
+            Colour::White => self.kind.to_symbol(),
+            Colour::Black => self.kind.to_symbol().to_ascii_lowercase(),
+        }
+    }
+
     pub fn new(kind: PieceKind, colour: Colour) -> Self {
         Self{kind, colour}
     }
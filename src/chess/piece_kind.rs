@@ -17,6 +17,16 @@ impl fmt::Debug for PieceKind {
 }
 
 impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [PieceKind::King, PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::Pawn];
+
+    pub const PROMOTIONS: [PieceKind; 4] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_index(&self) -> usize {
+        *self as usize
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_symbol(&self) -> char {
         match self {
             PieceKind::Rook => 'R',
@@ -19,6 +19,10 @@ impl Position {
         Self{x, y}
     }
 
+    pub fn from_index(index: usize) -> Self {
+        Self{x: index as i8 % SIZE, y: index as i8 / SIZE}
+    }
+
     pub fn get_index(&self) -> Option<usize> {
         if self.is_off_board() {
             return None;
@@ -37,6 +41,7 @@ impl Position {
         Some(Position{x: col as i8 - 97, y: row as i8 - 49})
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_symbol(&self) -> String {
         if self.is_off_board() {
             return String::default();
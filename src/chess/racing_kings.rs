@@ -0,0 +1,74 @@
+use super::bitboard::{EMPTY, RANK_8};
+use super::board::Board;
+use super::colour::Colour;
+use super::game_status::GameStatus;
+use super::piece::Piece;
+use super::piece_kind::PieceKind;
+use super::r#move::Move;
+use super::SIZE;
+
+impl Board {
+    // Neither king may ever be in check, so a move that gives check is as illegal as one that walks into it
+    pub fn racing_kings_moves(&self) -> Vec<Move> {
+        let mut moves = self.standard_moves();
+        let mut board = *self;
+        moves.retain(|m| {
+            let undo = board.make_move(*m);
+            let gives_check = board.is_check(board.turn);
+            board.unmake_move(undo);
+            !gives_check
+        });
+        moves
+    }
+
+    // The first king on the eighth rank wins, but when white gets there first black has one move to draw by arriving too
+    pub fn racing_kings_status(&self) -> Option<GameStatus> {
+        let white = self.pieces_of(PieceKind::King, Colour::White) & RANK_8 != EMPTY;
+        let black = self.pieces_of(PieceKind::King, Colour::Black) & RANK_8 != EMPTY;
+        let winner = match (white, black) {
+            (false, false) => return None,
+            (true, true) => return Some(GameStatus::VariantDraw),
+            (true, false) if self.turn == Colour::Black && self.can_reach_goal() => return None,
+            (true, false) => Colour::White,
+            (false, true) => Colour::Black,
+        };
+        Some(if winner == self.turn { GameStatus::VariantWin } else { GameStatus::VariantLoss })
+    }
+
+    // Whether the side to move has a legal king move onto the eighth rank
+    fn can_reach_goal(&self) -> bool {
+        let king = Piece::new(PieceKind::King, self.turn);
+        self.racing_kings_moves().iter().any(|m| self.get(m.from) == Some(king) && m.to.y == SIZE - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+    use crate::chess::variant::Variant;
+
+    fn racing_kings(fen: &str) -> Board {
+        fen_parser::parse_variant(fen, Variant::RacingKings).unwrap()
+    }
+
+    #[test]
+    fn giving_check_is_illegal() {
+        let moves = racing_kings("8/8/8/8/8/8/k7/6RK w - - 0 1").possible_moves();
+        assert!(!moves.contains(&Move::from_symbol("g1a1").unwrap()));
+        assert!(moves.contains(&Move::from_symbol("g1b1").unwrap()));
+    }
+
+    #[test]
+    fn black_gets_one_move_to_catch_up() {
+        assert_eq!(racing_kings("K7/8/7k/8/8/8/8/8 b - - 0 1").status(&[]), GameStatus::VariantLoss);
+        let mut board = racing_kings("K7/7k/8/8/8/8/8/8 b - - 0 1");
+        assert_eq!(board.status(&[]), GameStatus::Ongoing);
+        assert!(board.play_move(Move::from_symbol("h7h8").unwrap()));
+        assert_eq!(board.status(&[]), GameStatus::VariantDraw);
+        assert!(board.possible_moves().is_empty());
+        // Black loses when every step onto the eighth rank is covered
+        assert_eq!(racing_kings("K7/7k/8/8/8/8/8/B5R1 b - - 0 1").status(&[]), GameStatus::VariantLoss);
+        assert_eq!(racing_kings("7k/8/8/8/8/8/8/K7 w - - 0 1").status(&[]), GameStatus::VariantLoss);
+    }
+}
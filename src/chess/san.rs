@@ -0,0 +1,247 @@
+use super::board::Board;
+use super::piece_kind::PieceKind;
+use super::position::Position;
+use super::r#move::Move;
+
+impl Board {
+    // Writes a legal move in standard algebraic notation including the check or mate suffix
+    pub fn move_to_san(&self, m: Move) -> String {
+        let mut san = if let Some(kind) = m.drop {
+            format!("{}@{}", kind.to_symbol(), m.to.to_symbol())
+        } else if let Some(castling) = self.castling(m) {
+            String::from(if castling.is_king_side() { "O-O" } else { "O-O-O" })
+        } else {
+            let piece = self.get(m.from).expect("move_to_san needs a piece on the from square");
+            let capture = self.is_capture(m);
+            let mut san = String::default();
+            if piece.kind == PieceKind::Pawn {
+                if capture {
+                    san.push(file_symbol(m.from.x));
+                }
+            } else {
+                san.push(piece.kind.to_symbol());
+                san.push_str(&self.disambiguation(m, piece.kind));
+            }
+            if capture {
+                san.push('x');
+            }
+            san.push_str(&m.to.to_symbol());
+            if let Some(promote) = m.promote {
+                san.push('=');
+                san.push(promote.to_symbol());
+            }
+            san
+        };
+        let child = self.branch(m);
+        if child.is_check(child.turn) {
+            san.push(if child.possible_moves().is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+
+    // Finds the legal move described by a standard algebraic notation string, annotations such as + # ! ? are ignored
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(|c| "+#!?".contains(c));
+        let moves = self.possible_moves();
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle {
+            return moves.into_iter().find(|m| self.castling(*m).map(|castling| castling.is_king_side()) == Some(king_side));
+        }
+        // Crazyhouse drops as in N@f3, a bare @e4 drops a pawn
+        if let Some((kind, to)) = san.split_once('@') {
+            let kind = match kind {
+                "" => PieceKind::Pawn,
+                _ if kind.len() == 1 => PieceKind::from_symbol(kind.chars().next()?)?,
+                _ => return None,
+            };
+            let to = Position::from_symbol(to).filter(|pos| to.len() == 2 && !pos.is_off_board())?;
+            return moves.into_iter().find(|m| m.drop == Some(kind) && m.to == to);
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|c| !"x:-".contains(*c)).collect();
+        let kind = match chars.first() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let kind = PieceKind::from_symbol(*c)?;
+                chars.remove(0);
+                kind
+            },
+            _ => PieceKind::Pawn,
+        };
+        let promote = match chars.last() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let promote = PieceKind::from_symbol(*c)?;
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promote)
+            },
+            _ => None,
+        };
+        if chars.len() < 2 {
+            return None;
+        }
+        let to_symbol: String = chars[chars.len() - 2..].iter().collect();
+        let to = Position::from_symbol(&to_symbol).filter(|pos| !pos.is_off_board())?;
+        let mut from_x = None;
+        let mut from_y = None;
+        for c in chars[..chars.len() - 2].iter() {
+            match c {
+                'a'..='h' => from_x = Some(*c as i8 - 'a' as i8),
+                '1'..='8' => from_y = Some(*c as i8 - '1' as i8),
+                _ => return None,
+            }
+        }
+
+        let mut candidates = moves.into_iter().filter(|m| {
+            m.to == to
+                && m.promote == promote
+                && self.get(m.from).map(|p| p.kind) == Some(kind)
+                && from_x.is_none_or(|x| x == m.from.x)
+                && from_y.is_none_or(|y| y == m.from.y)
+                && self.castling(*m).is_none()
+        });
+        let m = candidates.next()?;
+        if candidates.next().is_some() {
+            return None;
+        }
+        Some(m)
+    }
+
+    // The file, rank or both of the from square when another piece of the same kind can reach the same square
+    fn disambiguation(&self, m: Move, kind: PieceKind) -> String {
+        let others: Vec<Position> = self.possible_moves().into_iter()
+            .filter(|other| other.to == m.to && other.from != m.from && self.get(other.from).map(|p| p.kind) == Some(kind))
+            .map(|other| other.from)
+            .collect();
+        if others.is_empty() {
+            String::default()
+        } else if others.iter().all(|pos| pos.x != m.from.x) {
+            file_symbol(m.from.x).to_string()
+        } else if others.iter().all(|pos| pos.y != m.from.y) {
+            rank_symbol(m.from.y).to_string()
+        } else {
+            m.from.to_symbol()
+        }
+    }
+}
+
+fn file_symbol(x: i8) -> char {
+    (b'a' + x as u8) as char
+}
+
+fn rank_symbol(y: i8) -> char {
+    (b'1' + y as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::board::Board;
+    use crate::chess::fen_parser;
+    use crate::chess::r#move::Move;
+    use crate::chess::variant::Variant;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    fn san(fen: &str, symbol: &str) -> String {
+        let board = fen_parser::parse(fen).unwrap();
+        board.move_to_san(Move::from_symbol(symbol).unwrap())
+    }
+
+    #[test]
+    fn formats_and_parses_drops() {
+        let board = fen_parser::parse_variant("4k3/8/8/8/8/8/8/4K3[Np] w - - 0 1", Variant::Crazyhouse).unwrap();
+        assert_eq!(board.move_to_san(Move::from_symbol("N@f6").unwrap()), "N@f6+");
+        assert_eq!(board.parse_san("N@f6+"), Move::from_symbol("N@f6"));
+        assert_eq!(board.parse_san("P@e4"), None);
+        let black = fen_parser::parse_variant("4k3/8/8/8/8/8/8/4K3[Np] b - - 0 1", Variant::Crazyhouse).unwrap();
+        assert_eq!(black.parse_san("@e4"), Move::from_symbol("P@e4"));
+        assert_eq!(black.move_to_san(Move::from_symbol("P@d2").unwrap()), "P@d2+");
+    }
+
+    #[test]
+    fn formats_pawn_and_piece_moves() {
+        assert_eq!(san("startpos", "e2e4"), "e4");
+        assert_eq!(san("startpos", "g1f3"), "Nf3");
+        assert_eq!(san("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "e4d5"), "exd5");
+        assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/3PP3/8/PPP2PPP/RNBQKBNR b KQkq d3 0 2", "f8b4"), "Bb4+");
+    }
+
+    #[test]
+    fn formats_en_passant() {
+        assert_eq!(san("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", "e5f6"), "exf6");
+    }
+
+    #[test]
+    fn formats_disambiguation() {
+        assert_eq!(san("4k3/8/8/8/8/5N2/8/RN2K2R w - - 0 1", "b1d2"), "Nbd2");
+        assert_eq!(san("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        assert_eq!(san("2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1", "h4e1"), "Qh4e1");
+        assert_eq!(san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"), "Rad1");
+    }
+
+    #[test]
+    fn formats_castling_promotion_and_mate() {
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"), "O-O-O");
+        assert_eq!(san("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q"), "bxa8=Q+");
+        assert_eq!(san("3k4/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"), "b8=N");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn parses_san() {
+        let board = fen_parser::parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let parse = |san: &str| board.parse_san(san).map(|m| m.to_symbol());
+        assert_eq!(parse("O-O"), Some(String::from("e1g1")));
+        assert_eq!(parse("0-0-0"), Some(String::from("e1c1")));
+        assert_eq!(parse("Nxf7"), Some(String::from("e5f7")));
+        assert_eq!(parse("Nf7!?"), Some(String::from("e5f7")));
+        assert_eq!(parse("Qxh3"), Some(String::from("f3h3")));
+        assert_eq!(parse("dxe6"), Some(String::from("d5e6")));
+        assert_eq!(parse("Rb1"), Some(String::from("a1b1")));
+        assert_eq!(parse("Nb5"), Some(String::from("c3b5")));
+        assert_eq!(parse("Nd3"), Some(String::from("e5d3")));
+        assert_eq!(parse("Ke2"), None);
+        assert_eq!(parse("e5"), None);
+        assert_eq!(parse("Zz9"), None);
+    }
+
+    #[test]
+    fn parse_rejects_ambiguous_moves() {
+        let board = fen_parser::parse("4k3/8/8/8/8/5N2/8/RN2K2R w - - 0 1").unwrap();
+        assert!(board.parse_san("Nd2").is_none());
+        assert!(board.parse_san("Nbd2").is_some());
+        assert!(board.parse_san("e8=Q").is_none());
+    }
+
+    #[test]
+    fn san_round_trips_possible_moves() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let fens = [
+            "startpos",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+        for fen in fens.iter() {
+            let mut board: Board = fen_parser::parse(fen).unwrap();
+            for _ in 0..40 {
+                let moves = board.possible_moves();
+                for m in moves.iter() {
+                    let san = board.move_to_san(*m);
+                    assert_eq!(board.parse_san(&san), Some(*m), "{} in {:?}", san, board);
+                }
+                match moves.choose(&mut rng) {
+                    Some(m) => board.play_move(*m),
+                    None => break,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use super::board::Board;
+use super::game_status::GameStatus;
+
+// Checks that win a three-check game
+pub const CHECKS_TO_WIN: u8 = 3;
+
+impl Board {
+    // Giving the third check wins straight away
+    pub fn three_check_status(&self) -> Option<GameStatus> {
+        if self.checks[self.turn.opposite().to_index()] >= CHECKS_TO_WIN {
+            Some(GameStatus::VariantLoss)
+        } else if self.checks[self.turn.to_index()] >= CHECKS_TO_WIN {
+            Some(GameStatus::VariantWin)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::check_make_unmake;
+    use crate::chess::colour::Colour;
+    use crate::chess::fen_parser;
+    use crate::chess::game_record::GameRecord;
+    use crate::chess::variant::Variant;
+
+    #[test]
+    fn counts_checks_until_the_third() {
+        let play = |board: Board, moves: &str| *GameRecord::from_moves(board, moves).unwrap().board();
+        let board = play(fen_parser::parse_variant("startpos", Variant::ThreeCheck).unwrap(), "e2e4 e7e5 f1b5 c7c6 b5c6 d7c6");
+        assert_eq!(board.checks, [0, 0]);
+        let board = play(board, "d1h5 g8f6 h5f7");
+        assert_eq!(board.checks[Colour::White.to_index()], 1);
+        assert_eq!(board.to_fen(), "rnbqkb1r/pp3Qpp/2p2n2/4p3/4P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 5 +1+0");
+        assert_eq!(board.status(&[]), GameStatus::Ongoing);
+        let board = fen_parser::parse_variant("4k3/8/8/8/8/8/8/4KQ2 b - - 0 1 +3+0", Variant::ThreeCheck).unwrap();
+        assert_eq!(board.status(&[]), GameStatus::VariantLoss);
+        assert!(board.possible_moves().is_empty());
+    }
+
+    #[test]
+    fn unmake_restores_check_counts() {
+        check_make_unmake("startpos", Variant::ThreeCheck, 8, 20, 80);
+    }
+}
@@ -0,0 +1,27 @@
+use super::castling::Castling;
+use super::bitboard::Bitboard;
+use super::piece::Piece;
+use super::position::Position;
+use super::r#move::Move;
+
+// The state make_move overwrites that cannot be worked out again from the move alone
+#[derive(Clone, Copy)]
+pub struct Undo {
+    pub m: Move,
+    pub moved: Piece,
+    pub captured: Option<(Piece, Position)>,
+    pub castling: Option<Castling>,
+    pub castle_white_king_side: bool,
+    pub castle_white_queen_side: bool,
+    pub castle_black_king_side: bool,
+    pub castle_black_queen_side: bool,
+    pub en_passant: Option<Position>,
+    pub half_move_number: usize,
+    pub move_number: usize,
+    pub pockets: [[u8; 6]; 2],
+    pub promoted: Bitboard,
+    pub checks: [u8; 2],
+    // Pieces an atomic capture blew up, the capturer included
+    pub exploded: [Option<(Piece, Position)>; 9],
+    pub hash: u64,
+}
@@ -0,0 +1,169 @@
+use super::bitboard::EMPTY;
+use super::board::Board;
+use super::game_status::GameStatus;
+use super::piece_kind::PieceKind;
+use super::r#move::Move;
+
+pub const STANDARD_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const CRAZYHOUSE_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1";
+const THREE_CHECK_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0";
+const RACING_KINGS_FEN: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+const HORDE_FEN: &str = "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+const ANTICHESS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+const ANTICHESS_PROMOTIONS: [PieceKind; 5] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::King];
+
+// The rules a board is played under, every rule that differs between variants is chosen by matching on this
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Variant {
+    Standard,
+    Chess960,
+    Crazyhouse,
+    Atomic,
+    Antichess,
+    KingOfTheHill,
+    ThreeCheck,
+    RacingKings,
+    Horde,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 9] = [
+        Variant::Standard, Variant::Chess960, Variant::Crazyhouse, Variant::Atomic, Variant::Antichess,
+        Variant::KingOfTheHill, Variant::ThreeCheck, Variant::RacingKings, Variant::Horde,
+    ];
+
+    // Lichess variant keys, from position games are played with standard rules
+    pub fn from_key(key: &str) -> Option<Variant> {
+        match key {
+            "standard" | "fromPosition" => Some(Variant::Standard),
+            "chess960" => Some(Variant::Chess960),
+            "crazyhouse" => Some(Variant::Crazyhouse),
+            "atomic" => Some(Variant::Atomic),
+            "antichess" => Some(Variant::Antichess),
+            "kingOfTheHill" => Some(Variant::KingOfTheHill),
+            "threeCheck" => Some(Variant::ThreeCheck),
+            "racingKings" => Some(Variant::RacingKings),
+            "horde" => Some(Variant::Horde),
+            _ => None,
+        }
+    }
+
+    // Lichess variant names as written in PGN Variant tags, such as "King of the Hill" or "Three-check"
+    pub fn from_name(name: &str) -> Option<Variant> {
+        let key: String = name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        if key.eq_ignore_ascii_case("fromPosition") {
+            return Some(Variant::Standard);
+        }
+        Variant::ALL.iter().copied().find(|variant| variant.key().eq_ignore_ascii_case(&key))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Chess960 => "chess960",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Atomic => "atomic",
+            Variant::Antichess => "antichess",
+            Variant::KingOfTheHill => "kingOfTheHill",
+            Variant::ThreeCheck => "threeCheck",
+            Variant::RacingKings => "racingKings",
+            Variant::Horde => "horde",
+        }
+    }
+
+    // The usual starting position, a Chess960 game needs its own FEN except for the one arrangement that matches standard
+    pub fn starting_fen(&self) -> &'static str {
+        match self {
+            Variant::Standard | Variant::Chess960 | Variant::Atomic | Variant::KingOfTheHill => STANDARD_FEN,
+            Variant::Crazyhouse => CRAZYHOUSE_FEN,
+            Variant::Antichess => ANTICHESS_FEN,
+            Variant::ThreeCheck => THREE_CHECK_FEN,
+            Variant::RacingKings => RACING_KINGS_FEN,
+            Variant::Horde => HORDE_FEN,
+        }
+    }
+}
+
+impl Board {
+    // Legal moves under the rules of the board's variant
+    pub fn possible_moves(&self) -> Vec<Move> {
+        match self.variant {
+            Variant::Standard | Variant::Chess960 | Variant::Crazyhouse | Variant::Horde => self.standard_moves(),
+            Variant::Atomic => self.atomic_moves(),
+            Variant::Antichess => self.antichess_moves(),
+            // Nothing more is played once a king is on the hill, the third check is given or the race is over
+            Variant::KingOfTheHill | Variant::ThreeCheck | Variant::RacingKings if self.variant_status().is_some() => Vec::default(),
+            Variant::KingOfTheHill | Variant::ThreeCheck => self.standard_moves(),
+            Variant::RacingKings => self.racing_kings_moves(),
+        }
+    }
+
+    // The pieces a pawn may become, antichess also allows a king
+    pub fn promotions(&self) -> &'static [PieceKind] {
+        match self.variant {
+            Variant::Antichess => &ANTICHESS_PROMOTIONS,
+            _ => &PieceKind::PROMOTIONS,
+        }
+    }
+
+    // Wins and losses particular to the variant, checked before checkmate and the draw rules
+    pub fn variant_status(&self) -> Option<GameStatus> {
+        match self.variant {
+            Variant::Standard | Variant::Chess960 | Variant::Crazyhouse => None,
+            // Losing the king to an explosion ends the game
+            Variant::Atomic if self.pieces_of(PieceKind::King, self.turn) == EMPTY => Some(GameStatus::VariantLoss),
+            Variant::Atomic => None,
+            Variant::Antichess => self.antichess_status(),
+            Variant::KingOfTheHill => self.king_of_the_hill_status(),
+            Variant::ThreeCheck => self.three_check_status(),
+            Variant::RacingKings => self.racing_kings_status(),
+            Variant::Horde => self.horde_status(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+
+    #[test]
+    fn keys_round_trip() {
+        for variant in Variant::ALL.iter() {
+            assert_eq!(Variant::from_key(variant.key()), Some(*variant));
+        }
+        assert_eq!(Variant::from_key("fromPosition"), Some(Variant::Standard));
+        assert_eq!(Variant::from_key("unknown"), None);
+    }
+
+    #[test]
+    fn reads_pgn_names() {
+        assert_eq!(Variant::from_name("Chess960"), Some(Variant::Chess960));
+        assert_eq!(Variant::from_name("Three-check"), Some(Variant::ThreeCheck));
+        assert_eq!(Variant::from_name("King of the Hill"), Some(Variant::KingOfTheHill));
+        assert_eq!(Variant::from_name("Racing Kings"), Some(Variant::RacingKings));
+        assert_eq!(Variant::from_name("From Position"), Some(Variant::Standard));
+        assert_eq!(Variant::from_name("Bughouse"), None);
+    }
+
+    #[test]
+    fn chess960_castles_onto_the_rook_from_standard_squares() {
+        let board = fen_parser::parse_variant("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", Variant::Chess960).unwrap();
+        let castles: Vec<String> = board.possible_moves().into_iter()
+            .filter(|m| board.castling(*m).is_some())
+            .map(|m| m.to_symbol())
+            .collect();
+        assert_eq!(castles, vec!["e1h1", "e1a1"]);
+        assert_eq!(board.variant, Variant::Chess960);
+    }
+
+    #[test]
+    fn startpos_uses_the_variant_start() {
+        let board = fen_parser::parse_variant("startpos", Variant::Standard).unwrap();
+        assert_eq!(board.to_fen(), STANDARD_FEN);
+        assert_eq!(board.possible_moves().len(), 20);
+        for variant in [Variant::Crazyhouse, Variant::Antichess, Variant::ThreeCheck, Variant::RacingKings, Variant::Horde].iter() {
+            assert_eq!(fen_parser::parse_variant("startpos", *variant).unwrap().to_fen(), variant.starting_fen());
+        }
+    }
+}
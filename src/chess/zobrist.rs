@@ -0,0 +1,72 @@
+use super::colour::Colour;
+use super::piece::Piece;
+use super::piece_kind::PieceKind;
+use super::SQUARE_SIZE;
+
+// Fixed seed so keys, and therefore hashes, are the same on every run
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+const PIECE_KEYS: [[[u64; SQUARE_SIZE]; 6]; 2] = piece_keys();
+const CASTLING_KEYS: [u64; 4] = [key(SEED, 768), key(SEED, 769), key(SEED, 770), key(SEED, 771)];
+const EN_PASSANT_KEYS: [u64; 8] = en_passant_keys();
+pub const BLACK_TO_MOVE: u64 = key(SEED, 780);
+
+pub const CASTLE_WHITE_KING_SIDE: usize = 0;
+pub const CASTLE_WHITE_QUEEN_SIDE: usize = 1;
+pub const CASTLE_BLACK_KING_SIDE: usize = 2;
+pub const CASTLE_BLACK_QUEEN_SIDE: usize = 3;
+
+// SplitMix64 step, the nth key is the output after n + 1 increments of the state
+const fn key(seed: u64, n: u64) -> u64 {
+    let mut z = seed.wrapping_add((n + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[[u64; SQUARE_SIZE]; 6]; 2] {
+    let mut keys = [[[0; SQUARE_SIZE]; 6]; 2];
+    let mut n = 0;
+    while n < 2 * 6 * SQUARE_SIZE {
+        keys[n / (6 * SQUARE_SIZE)][n / SQUARE_SIZE % 6][n % SQUARE_SIZE] = key(SEED, n as u64);
+        n += 1;
+    }
+    keys
+}
+
+const fn en_passant_keys() -> [u64; 8] {
+    let mut keys = [0; 8];
+    let mut n = 0;
+    while n < 8 {
+        keys[n] = key(SEED, 772 + n as u64);
+        n += 1;
+    }
+    keys
+}
+
+pub fn piece(piece: Piece, index: usize) -> u64 {
+    PIECE_KEYS[piece.colour.to_index()][piece.kind.to_index()][index]
+}
+
+pub fn castling(right: usize) -> u64 {
+    CASTLING_KEYS[right]
+}
+
+pub fn en_passant_file(x: i8) -> u64 {
+    EN_PASSANT_KEYS[x as usize]
+}
+
+// Crazyhouse pockets, one key for each count of each piece so the hash tells apart one pawn in hand from two
+pub fn pocket(colour: Colour, kind: PieceKind, count: u8) -> u64 {
+    key(SEED, 781 + ((colour.to_index() * 6 + kind.to_index()) * 32 + count as usize) as u64)
+}
+
+// Crazyhouse pieces that were promoted and go back to the pocket as pawns when captured
+pub fn promoted(index: usize) -> u64 {
+    key(SEED, 781 + 2 * 6 * 32 + index as u64)
+}
+
+// Three-check counts of checks given, up to three for each colour
+pub fn checks(colour: Colour, count: u8) -> u64 {
+    key(SEED, 781 + 2 * 6 * 32 + 64 + (colour.to_index() * 4 + count as usize) as u64)
+}
@@ -16,6 +16,7 @@ pub struct LichessConfig {
     pub my_id: String,
     pub base_url: String,
     pub challenge_filter: ChallengeFilter,
+    pub pgn_directory: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,9 +33,8 @@ pub fn load_env() -> std::io::Result<Config> {
 }
 
 fn open_env() -> std::io::Result<File> {
-    File::open(ENV_PATH).or_else(|e| {
+    File::open(ENV_PATH).inspect_err(|_| {
         create_env();
-        Err(e)
     })
 }
 
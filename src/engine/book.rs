@@ -0,0 +1,210 @@
+use std::fs;
+use std::io;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::chess::bitboard::{self, EMPTY};
+use crate::chess::board::Board;
+use crate::chess::colour::Colour;
+use crate::chess::piece_kind::PieceKind;
+use crate::chess::position::Position;
+use crate::chess::r#move::Move;
+use crate::chess::variant::Variant;
+use super::polyglot_keys::RANDOM;
+
+pub const ENTRY_SIZE: usize = 16;
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const WHITE_TO_MOVE: usize = 780;
+
+// One Polyglot book entry, stored as 16 big endian bytes and sorted by key in the file
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open(path: &str) -> io::Result<Book> {
+        Ok(Self::from_bytes(&fs::read(path)?))
+    }
+
+    // A trailing partial entry is ignored
+    pub fn from_bytes(bytes: &[u8]) -> Book {
+        let entries = bytes.chunks_exact(ENTRY_SIZE).map(|chunk| BookEntry{
+            key: u64::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]),
+            raw_move: u16::from_be_bytes([chunk[8], chunk[9]]),
+            weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+            learn: u32::from_be_bytes([chunk[12], chunk[13], chunk[14], chunk[15]]),
+        }).collect();
+        Book{entries}
+    }
+
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    // Legal book moves for the position with their weights, Polyglot keys only describe standard chess
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        if board.variant != Variant::Standard {
+            return Vec::default();
+        }
+        let legal = board.possible_moves();
+        self.entries(key(board)).iter()
+            .filter_map(|entry| Some((decode_move(board, &legal, entry.raw_move)?, entry.weight)))
+            .collect()
+    }
+
+    // Picks a book move at random in proportion to its weight, skipping moves weighted below the minimum
+    pub fn choose<R: Rng>(&self, board: &Board, min_weight: u16, rng: &mut R) -> Option<Move> {
+        let moves: Vec<(Move, u16)> = self.moves(board).into_iter()
+            .filter(|(_, weight)| *weight > 0 && *weight >= min_weight)
+            .collect();
+        moves.choose_weighted(rng, |(_, weight)| *weight).ok().map(|(m, _)| *m)
+    }
+}
+
+// The Polyglot hash of a position, which differs from our own zobrist hash only in its keys
+pub fn key(board: &Board) -> u64 {
+    let mut key = 0;
+    for index in 0..64 {
+        if let Some(piece) = board.get(Position::from_index(index)) {
+            let kind = match piece.kind {
+                PieceKind::Pawn => 0,
+                PieceKind::Knight => 1,
+                PieceKind::Bishop => 2,
+                PieceKind::Rook => 3,
+                PieceKind::Queen => 4,
+                PieceKind::King => 5,
+            };
+            let colour = if piece.colour == Colour::White { 1 } else { 0 };
+            key ^= RANDOM[64 * (2 * kind + colour) + index];
+        }
+    }
+    for right in 0..4 {
+        if board.can_castle(right) {
+            key ^= RANDOM[CASTLING_OFFSET + right];
+        }
+    }
+    // Polyglot also only counts the en passant file when a pawn of the side to move could capture
+    if let Some((pos, index)) = board.en_passant.and_then(|pos| Some((pos, pos.get_index()?))) {
+        if bitboard::pawn_attacks(board.turn.opposite(), index) & board.pieces_of(PieceKind::Pawn, board.turn) != EMPTY {
+            key ^= RANDOM[EN_PASSANT_OFFSET + pos.x as usize];
+        }
+    }
+    if board.turn == Colour::White {
+        key ^= RANDOM[WHITE_TO_MOVE];
+    }
+    key
+}
+
+// Book moves are packed as to file, to rank, from file, from rank and promotion in three bits each,
+// castling is written as the king taking its own rook
+pub fn encode_move(board: &Board, m: Move) -> u16 {
+    let to = board.castling(m).map_or(m.to, |castling| castling.rook_from);
+    let square = |pos: Position| (pos.x | pos.y << 3) as u16;
+    let promote = match m.promote {
+        Some(PieceKind::Knight) => 1,
+        Some(PieceKind::Bishop) => 2,
+        Some(PieceKind::Rook) => 3,
+        Some(PieceKind::Queen) => 4,
+        _ => 0,
+    };
+    square(to) | square(m.from) << 6 | promote << 12
+}
+
+fn decode_move(board: &Board, legal: &[Move], raw_move: u16) -> Option<Move> {
+    let square = |shift: u16| Position::new((raw_move >> shift & 7) as i8, (raw_move >> (shift + 3) & 7) as i8);
+    let to = square(0);
+    let from = square(6);
+    let promote = match raw_move >> 12 & 7 {
+        0 => None,
+        1 => Some(PieceKind::Knight),
+        2 => Some(PieceKind::Bishop),
+        3 => Some(PieceKind::Rook),
+        4 => Some(PieceKind::Queen),
+        _ => return None,
+    };
+    legal.iter().copied().find(|m| {
+        let castle_to = board.castling(*m).map(|castling| castling.rook_from);
+        m.from == from && m.promote == promote && (castle_to == Some(to) || (castle_to.is_none() && m.to == to))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::chess::fen_parser;
+
+    const TEST_BOOK: &str = "books/test.bin";
+
+    #[test]
+    fn matches_polyglot_keys() {
+        let cases = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b_9618_1691_fc9c),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c_9b50_fd11_4196),
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756_b944_61c5_0fb0),
+            ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662f_afb9_65db_29d4),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a4_8b5a_8e47_ff78),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a_607c_a3f2_42c1),
+            ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fd_d303_c946_bdd9),
+            ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c81_23ea_7b06_7637),
+            ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f_9b82_9b27_9560),
+        ];
+        for (fen, expected) in cases.iter() {
+            assert_eq!(key(&fen_parser::parse(fen).unwrap()), *expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn reads_book_moves() {
+        let book = Book::open(TEST_BOOK).unwrap();
+        let board = fen_parser::parse("startpos").unwrap();
+        let mut moves: Vec<(String, u16)> = book.moves(&board).into_iter().map(|(m, weight)| (m.to_symbol(), weight)).collect();
+        moves.sort();
+        assert_eq!(moves, vec![(String::from("d2d4"), 10), (String::from("e2e4"), 30), (String::from("g1f3"), 1)]);
+        assert!(book.moves(&fen_parser::parse_variant("startpos", Variant::Chess960).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn decodes_castling_as_king_takes_rook() {
+        let book = Book::open(TEST_BOOK).unwrap();
+        let board = fen_parser::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let moves: Vec<String> = book.moves(&board).into_iter().map(|(m, _)| m.to_symbol()).collect();
+        assert_eq!(moves, vec!["e1g1"]);
+        assert_eq!(encode_move(&board, Move::from_symbol("e1g1").unwrap()), 0x0107);
+    }
+
+    #[test]
+    fn chooses_weighted_moves_above_minimum() {
+        let book = Book::open(TEST_BOOK).unwrap();
+        let board = fen_parser::parse("startpos").unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..20 {
+            let m = book.choose(&board, 10, &mut rng).unwrap().to_symbol();
+            assert!(m == "e2e4" || m == "d2d4");
+        }
+        assert_eq!(book.choose(&board, 31, &mut rng), None);
+        assert_eq!(book.choose(&fen_parser::parse("8/8/8/8/8/8/8/K6k w - - 0 1").unwrap(), 0, &mut rng), None);
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::chess::colour::Colour;
+use crate::chess::pgn::{self, PgnGame};
+use super::book::{self, BookEntry};
+
+// Wins, draws and losses for the side that played a move
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct MoveStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveStats {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Half points scored, so a move's score and how often it was played both raise its weight
+    pub fn weight(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+}
+
+pub struct BookBuilder {
+    pub max_plies: usize,
+    // Moves played in fewer games than this are left out of the book
+    pub min_games: u32,
+    // Only moves played by this colour are recorded, for building a repertoire for one side
+    pub colour: Option<Colour>,
+    stats: HashMap<(u64, u16), MoveStats>,
+}
+
+impl BookBuilder {
+    pub fn new(max_plies: usize, min_games: u32, colour: Option<Colour>) -> Self {
+        BookBuilder{max_plies, min_games, colour, stats: HashMap::new()}
+    }
+
+    // Records the first max_plies mainline moves of a finished standard game, returns whether the game was used
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        let variant = game.tag("Variant").unwrap_or("Standard");
+        if !variant.eq_ignore_ascii_case("standard") && !variant.eq_ignore_ascii_case("from position") {
+            return false;
+        }
+        let winner = match game.result.as_deref() {
+            Some("1-0") => Some(Colour::White),
+            Some("0-1") => Some(Colour::Black),
+            Some("1/2-1/2") => None,
+            _ => return false,
+        };
+        for (board, m) in game.boards().iter().zip(game.moves.iter()).take(self.max_plies) {
+            if self.colour.is_none() || self.colour == Some(board.turn) {
+                let stats = self.stats.entry((book::key(board), book::encode_move(board, *m))).or_default();
+                match winner {
+                    Some(colour) if colour == board.turn => stats.wins += 1,
+                    Some(_) => stats.losses += 1,
+                    None => stats.draws += 1,
+                }
+            }
+        }
+        true
+    }
+
+    // Returns the number of games used and the number of games that could not be read
+    pub fn add_pgn(&mut self, pgn: &str) -> (usize, usize) {
+        let mut used = 0;
+        let mut errors = 0;
+        for game in pgn::parse(pgn) {
+            match game {
+                Ok(game) => if self.add_game(&game) { used += 1 },
+                Err(_) => errors += 1,
+            }
+        }
+        (used, errors)
+    }
+
+    pub fn add_directory(&mut self, directory: &str) -> io::Result<(usize, usize)> {
+        let mut totals = (0, 0);
+        let mut paths: Vec<_> = fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "pgn"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let (used, errors) = self.add_pgn(&fs::read_to_string(path)?);
+            totals = (totals.0 + used, totals.1 + errors);
+        }
+        Ok(totals)
+    }
+
+    // Book entries sorted by key then by weight, weights of each position are scaled down together to fit in 16 bits
+    pub fn entries(&self) -> Vec<BookEntry> {
+        let mut positions: HashMap<u64, Vec<(u16, u32)>> = HashMap::new();
+        for ((key, raw_move), stats) in self.stats.iter() {
+            if stats.games() >= self.min_games && stats.weight() > 0 {
+                positions.entry(*key).or_default().push((*raw_move, stats.weight()));
+            }
+        }
+        let mut entries: Vec<BookEntry> = positions.into_iter().flat_map(|(key, moves)| {
+            let max = moves.iter().map(|(_, weight)| *weight).max().unwrap_or(0);
+            let scale = if max > u16::MAX as u32 { u16::MAX as f64 / max as f64 } else { 1f64 };
+            moves.into_iter().map(move |(raw_move, weight)| BookEntry{
+                key,
+                raw_move,
+                weight: ((weight as f64 * scale) as u16).max(1),
+                learn: 0,
+            })
+        }).collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)).then(a.raw_move.cmp(&b.raw_move)));
+        entries
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<usize> {
+        let entries = self.entries();
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, entries.iter().flat_map(|entry| entry.to_bytes().to_vec()).collect::<Vec<u8>>())?;
+        Ok(entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::book::Book;
+    use crate::chess::fen_parser;
+
+    const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result "0-1"]
+
+1. e4 c5 2. Nf3 0-1
+
+[Result "1/2-1/2"]
+
+1. d4 d5 1/2-1/2
+
+[Variant "Atomic"]
+[Result "1-0"]
+
+1. e4 e5 1-0
+
+[Result "*"]
+
+1. c4 *
+"#;
+
+    fn book_moves(builder: &BookBuilder, fen: &str) -> Vec<(String, u16)> {
+        let mut bytes = Vec::default();
+        for entry in builder.entries() {
+            bytes.extend_from_slice(&entry.to_bytes());
+        }
+        let book = Book::from_bytes(&bytes);
+        book.moves(&fen_parser::parse(fen).unwrap()).into_iter().map(|(m, weight)| (m.to_symbol(), weight)).collect()
+    }
+
+    #[test]
+    fn aggregates_results_per_move() {
+        let mut builder = BookBuilder::new(2, 1, None);
+        assert_eq!(builder.add_pgn(GAMES), (3, 0));
+        assert_eq!(book_moves(&builder, "startpos"), vec![(String::from("e2e4"), 2), (String::from("d2d4"), 1)]);
+        let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(book_moves(&builder, after_e4), vec![(String::from("c7c5"), 2)]);
+        // Past the ply limit nothing is recorded
+        assert!(book_moves(&builder, "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").is_empty());
+    }
+
+    #[test]
+    fn filters_by_colour_and_game_count() {
+        let mut builder = BookBuilder::new(10, 1, Some(Colour::Black));
+        builder.add_pgn(GAMES);
+        assert!(book_moves(&builder, "startpos").is_empty());
+        let after_d4 = "rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1";
+        assert_eq!(book_moves(&builder, after_d4), vec![(String::from("d7d5"), 1)]);
+
+        let mut builder = BookBuilder::new(10, 2, None);
+        builder.add_pgn(GAMES);
+        assert_eq!(book_moves(&builder, "startpos"), vec![(String::from("e2e4"), 2)]);
+    }
+
+    #[test]
+    fn scales_weights_to_sixteen_bits() {
+        let mut builder = BookBuilder::new(1, 1, None);
+        let won = "[Result \"1-0\"]\n\n1. e4 1-0\n\n";
+        let drawn = "[Result \"1/2-1/2\"]\n\n1. d4 1/2-1/2\n\n";
+        builder.add_pgn(&won.repeat(40000));
+        builder.add_pgn(&drawn.repeat(1000));
+        let entries = builder.entries();
+        assert_eq!(entries.iter().map(|entry| entry.weight).collect::<Vec<u16>>(), vec![u16::MAX, 819]);
+    }
+}
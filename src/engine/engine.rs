@@ -2,33 +2,42 @@ extern crate rand;
 
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::time::Instant;
-use std::collections::BinaryHeap;
-use super::board_frame::BoardFrame;
-use super::evaluator::MaterialEvaluator;
+use super::book::Book;
 use super::engine_config::EngineConfig;
 use super::game::Game;
-use super::evaluated::Evaluated;
-use crate::chess::{board::Board, r#move::Move, fen_parser, colour::Colour};
+use super::search::Search;
+use super::search_info::SearchInfo;
+use super::transposition::TranspositionTable;
+use crate::chess::{board::Board, r#move::Move, fen_parser, colour::Colour, game_record::GameRecord, game_status::GameStatus};
 
 pub struct Engine {
-    frame_queue: BinaryHeap<BoardFrame>,
-    max_depth_reached: usize,
-    moves_analysed: u128,
     config: EngineConfig,
+    book: Option<Book>,
+    table: TranspositionTable,
 }
 
 impl Engine {
+    fn new(config: EngineConfig) -> Self {
+        let book = config.book_path.and_then(|path| match Book::open(path) {
+            Ok(book) => Some(book),
+            Err(e) => {
+                println!("No opening book at {}: {}", path, e);
+                None
+            },
+        });
+        Engine{
+            config,
+            book,
+            table: TranspositionTable::new(config.hash_size),
+        }
+    }
+
     pub fn channels() -> (Sender<Game>, Receiver<Game>) {
         channel::<Game>()
     }
 
     pub fn run_fen_engine(fen: String, config: EngineConfig) -> Option<Move> {
-        let mut engine = Engine{
-            frame_queue: BinaryHeap::new(),
-            max_depth_reached: 0,
-            moves_analysed: 0,
-            config,
-        };
+        let mut engine = Engine::new(config);
 
         let board: Board = fen_parser::parse(&fen).unwrap();
         let side = board.turn;
@@ -37,17 +46,13 @@ impl Engine {
             initial_board: board,
             moves: String::default(),
             my_side: side,
+            search_info: None,
         };
         engine.receive_game(game)
     }
 
     pub fn start_receiver_engine(rx: Receiver<Game>, perform_move: fn(&str, &str) -> bool, config: EngineConfig) {
-        let mut engine = Engine{
-            frame_queue: BinaryHeap::new(),
-            max_depth_reached: 0,
-            moves_analysed: 0,
-            config,
-        };
+        let mut engine = Engine::new(config);
         for game in rx.iter() {
             let game_id = &game.game_id.clone();
             let best_move = engine.receive_game(game);
@@ -59,91 +64,51 @@ impl Engine {
     }
 
     fn receive_game(&mut self, game: Game) -> Option<Move> {
-        let mut board = game.initial_board;
-        if !&board.parse_moves(&game.moves) {
+        let record = GameRecord::from_moves(game.initial_board, &game.moves)?;
+        let board = *record.board();
+        if board.turn != game.my_side || record.status() != GameStatus::Ongoing {
             return None;
         }
-        if board.turn != game.my_side {
-            return None;
+        if let Some(m) = self.book_move(&board) {
+            println!("Position: {}", board.to_fen());
+            println!("Book move: {}", board.move_to_san(m));
+            return Some(m);
         }
 
-        let (tx, rx): (Sender<Evaluated>, Receiver<Evaluated>) = channel();
         let now = Instant::now();
-        let frame = BoardFrame::new(board, game.my_side, self.config, tx, MaterialEvaluator::evaluate(&board, board.turn));
-        self.frame_queue.push(frame);
-        self.process_frames();
-        let x = rx.recv().unwrap();
-        println!("Moves analysed: {}", self.moves_analysed);
-        println!("Max depth: {}", self.max_depth_reached);
-        println!("Result evaluation: {:?}", x.current_eval);
-        println!("Best line: {:?}", x.moves);
+        let mut search = Search::new(board, record.history().to_vec(), self.config, &mut self.table);
+        let result = search.iterate(|iteration| {
+            println!("Depth {} score {:.2} nodes {} pv {:?}", iteration.depth, iteration.score, iteration.stats.nodes, iteration.pv);
+        })?;
+        let best_move = result.pv[0];
+        // Scores are reported from White's point of view
+        let side = board.turn.to_num();
+        println!("Position: {}", board.to_fen());
+        println!("Moves analysed: {}", result.stats.nodes);
+        println!("First move cutoffs: {:.1}% of {}", 100f64 * result.stats.first_move_cutoff_rate(), result.stats.cutoffs);
+        println!("Depth: {}", result.depth);
+        println!("Result evaluation: {:?}", side as f64 * result.score);
+        println!("Best move: {}", board.move_to_san(best_move));
+        println!("Best line: {:?}", result.pv);
+        println!("Hash full: {}/1000", self.table.hashfull());
         println!("Elapsed: {}", (Instant::now() - now).as_secs_f32());
-        Some(x.moves[0])
+        if let Some(search_info) = game.search_info {
+            search_info.send(SearchInfo{
+                ply: game.moves.split(' ').filter(|m| !m.is_empty()).count(),
+                best_move,
+                evaluation: side as f64 * result.score,
+                mate_in: result.mate_in().map(|mate_in| side * mate_in),
+                depth: result.depth,
+            }).unwrap_or(());
+        }
+        Some(best_move)
     }
-}
 
-impl Engine {
-    fn process_frames(&mut self) {
-        println!("Processing Frames");
-        while let Some(mut frame) = self.frame_queue.pop() {
-            
-            let now = Instant::now();
-            // println!("{:?} {}", frame.moves, frame.child_count);
-            if frame.child_count == 0 {
-                if frame.time < now || (frame.deep_time < now && frame.depth >= self.config.deep_depth) {
-                    frame.evaluation.send(Evaluated{moves: frame.moves.clone(), current_eval: MaterialEvaluator::evaluate(&frame.board, Colour::White)}).unwrap_or(());
-                } else {
-                    if frame.board.is_checkmate() {
-                        frame.evaluation.send(Evaluated{moves: frame.moves.clone(), current_eval: frame.side.to_num() as f64 * -10000f64}).unwrap();
-                        continue;
-                    }
-                    let pos_moves = frame.board.possible_moves();
-                    self.moves_analysed += 1;
-                    if self.max_depth_reached < frame.moves.len() {
-                        self.max_depth_reached = frame.moves.len();
-                    }
-                    let (tx, rx): (Sender<Evaluated>, Receiver<Evaluated>) = channel();
-                    frame.child_count = pos_moves.len();
-                    frame.children = Some(rx);
-                    for i in pos_moves {
-                        let mut c: BoardFrame = frame.branch(i, tx.clone());
-                        c.evaluation = tx.clone();
-                        self.frame_queue.push(c);
-                    }
-                    self.frame_queue.push(frame);
-                }
-            } else {
-                match frame.children {
-                    Some(ref rx) => {
-                        loop {
-                            if frame.child_count > 0 {
-                                match rx.try_recv() {
-                                    Ok(v) => {
-                                        frame.child_count -= 1;
-                                        if frame.current_eval.is_none() {
-                                            frame.current_eval = Some(v);
-                                            continue;
-                                        }
-                                        let eval = frame.current_eval.clone().unwrap();
-                                        if (frame.side == Colour::White && eval.current_eval < v.current_eval) || (frame.side == Colour::Black && eval.current_eval > v.current_eval) {
-                                            frame.current_eval = Some(v);
-                                        }
-                                    },
-                                    Err(_) => {
-                                        frame.delay += 1;
-                                        self.frame_queue.push(frame);
-                                        break;
-                                    }
-                                }
-                            } else {
-                                frame.evaluation.send(frame.current_eval.unwrap()).unwrap();
-                                break;
-                            }
-                        }
-                    },
-                    None => (),
-                }
-            }
+    fn book_move(&self, board: &Board) -> Option<Move> {
+        let ply = (board.move_number - 1) * 2 + if board.turn == Colour::Black { 1 } else { 0 };
+        if ply >= self.config.book_max_depth {
+            return None;
         }
+        self.book.as_ref()?.choose(board, self.config.book_min_weight, &mut rand::thread_rng())
     }
-}
\ No newline at end of file
+}
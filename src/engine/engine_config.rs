@@ -1,27 +1,49 @@
-
 #[derive(Clone, Copy)]
 pub struct EngineConfig {
+    #[allow(dead_code)]
     pub debug: bool,
     pub time: f32,
-    pub deep_frac: f32,
-    pub deep_depth: isize,
+    // Searches stop at this depth and ignore the clock until they reach it, so their results can be repeated
+    pub depth: Option<usize>,
+    // Quiescence search also tries quiet moves that give check at its first ply
+    pub quiescence_checks: bool,
+    // Polyglot opening book, played from until the position is past book_max_depth plies or out of book
+    pub book_path: Option<&'static str>,
+    pub book_max_depth: usize,
+    // Book moves weighted below this are never played
+    pub book_min_weight: u16,
+    // Size of the transposition table in megabytes
+    pub hash_size: usize,
+    // Selective search techniques, each can be turned off to measure what it is worth
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub reverse_futility_pruning: bool,
+    pub futility_pruning: bool,
 }
 
 impl EngineConfig {
-    pub fn new(time: f32, deep_frac: f32, deep_depth: isize, debug: bool) -> Self {
+    pub fn new(time: f32, debug: bool) -> Self {
         EngineConfig{
             time,
-            deep_frac,
-            deep_depth,
             debug,
+            depth: None,
+            quiescence_checks: true,
+            book_path: None,
+            book_max_depth: 20,
+            book_min_weight: 1,
+            hash_size: 64,
+            null_move_pruning: true,
+            late_move_reductions: true,
+            reverse_futility_pruning: true,
+            futility_pruning: true,
         }
     }
 
     pub fn default() -> Self {
-        Self::new(10f32, 0.5f32, 3, false)
+        Self::new(10f32, false)
     }
 
     pub fn default_debug(debug: bool) -> Self {
-        Self::new(20f32, 0.5f32, 3, debug)
+        Self::new(20f32, debug)
     }
-}
\ No newline at end of file
+}
@@ -2,23 +2,50 @@ use crate::chess::board::Board;
 use crate::chess::colour::Colour;
 use crate::chess::piece::Piece;
 use crate::chess::piece_kind::PieceKind;
+use crate::chess::variant::Variant;
 
+// Pawns a king gains for each step towards the hill
+const HILL_STEP_VALUE: f64 = 0.5;
+// Pawns each check given is worth, the third one wins the game
+const CHECK_VALUE: f64 = 2.0;
 
 pub struct MaterialEvaluator {}
 
 impl MaterialEvaluator {
     pub fn evaluate(board: &Board, side: Colour) -> f64 {
-        let mut sum = 0;
+        // Crazyhouse pockets can hold more material than fits in the i8 piece values
+        let mut sum: i32 = 0;
         for pos in board.position_iter() {
             sum += match board.get(pos) {
-                Some(p) => (if p.colour == side {1} else {-1}) * Self::get_piece_value(p),
+                Some(p) => (if p.colour == side {1} else {-1}) * Self::get_piece_value(p) as i32,
                 None => 0,
             };
         }
-        sum as f64
+        for colour in [Colour::White, Colour::Black].iter() {
+            for kind in PieceKind::ALL.iter() {
+                let count = board.pockets[colour.to_index()][kind.to_index()] as i32;
+                sum += (if *colour == side {1} else {-1}) * count * Self::get_piece_value(Piece::new(*kind, *colour)) as i32;
+            }
+        }
+        // Antichess is won by losing material so the count is turned around
+        let material = match board.variant {
+            Variant::Antichess => -sum as f64,
+            _ => sum as f64,
+        };
+        material + Self::variant_terms(board, side)
+    }
+
+    // A king closer to the hill in king of the hill and every check already given in three-check
+    fn variant_terms(board: &Board, side: Colour) -> f64 {
+        let sign = |colour: Colour| if colour == side {1f64} else {-1f64};
+        [Colour::White, Colour::Black].iter().map(|colour| sign(*colour) * match board.variant {
+            Variant::KingOfTheHill => board.hill_distance(*colour).map_or(0f64, |distance| HILL_STEP_VALUE * (3 - distance) as f64),
+            Variant::ThreeCheck => CHECK_VALUE * board.checks[colour.to_index()] as f64,
+            _ => 0f64,
+        }).sum()
     }
 
-    fn get_piece_value(piece: Piece) -> i8 {
+    pub fn get_piece_value(piece: Piece) -> i8 {
         match piece.kind {
             PieceKind::King => 0,
             PieceKind::Queen => 9,
@@ -28,4 +55,17 @@ impl MaterialEvaluator {
             PieceKind::Pawn => 1,
         }
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+
+    #[test]
+    fn counts_large_pockets() {
+        let board = fen_parser::parse_variant("4k3/8/8/8/8/8/8/4K3[QQQQQQQQQQQQQQQ] w - - 0 1", Variant::Crazyhouse).unwrap();
+        assert_eq!(MaterialEvaluator::evaluate(&board, Colour::White), 135f64);
+        assert_eq!(MaterialEvaluator::evaluate(&board, Colour::Black), -135f64);
+    }
+}
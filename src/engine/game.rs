@@ -1,9 +1,12 @@
 use crate::chess::board::Board;
 use crate::chess::colour::Colour;
+use super::search_info::SearchInfo;
+use std::sync::mpsc::Sender;
 
 pub struct Game {
     pub game_id: String,
     pub initial_board: Board,
     pub moves: String,
     pub my_side: Colour,
+    pub search_info: Option<Sender<SearchInfo>>,
 }
\ No newline at end of file
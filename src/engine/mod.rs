@@ -1,7 +1,13 @@
-pub mod board_frame;
+#[allow(clippy::module_inception)]
 pub mod engine;
 pub mod game;
 pub mod evaluator;
 pub mod engine_config;
 pub mod puzzles;
-pub mod evaluated;
\ No newline at end of file
+pub mod search_info;
+pub mod book;
+pub mod book_builder;
+pub mod polyglot_keys;
+pub mod search;
+pub mod transposition;
+pub mod move_picker;
@@ -0,0 +1,194 @@
+use crate::chess::board::Board;
+use crate::chess::colour::Colour;
+use crate::chess::piece::Piece;
+use crate::chess::piece_kind::PieceKind;
+use crate::chess::position::Position;
+use crate::chess::r#move::Move;
+use super::evaluator::MaterialEvaluator;
+
+// History scores are halved once one passes this so recent cutoffs count for more
+const HISTORY_LIMIT: i32 = 1 << 20;
+
+// The order moves are handed out in, each stage is only sorted when it is reached
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stage {
+    HashMove,
+    // Captures taking a piece worth at least the capturer or taking on an undefended square
+    GoodCaptures,
+    Promotions,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// How often each quiet move has caused a cutoff, by colour, from square and to square
+pub struct History {
+    scores: Vec<i32>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History{scores: vec![0; 2 * 64 * 64]}
+    }
+
+    fn index(colour: Colour, m: Move) -> usize {
+        let square = |pos: Position| pos.get_index().unwrap_or(0);
+        colour.to_index() * 64 * 64 + square(m.from) * 64 + square(m.to)
+    }
+
+    pub fn get(&self, colour: Colour, m: Move) -> i32 {
+        self.scores[Self::index(colour, m)]
+    }
+
+    // Deeper cutoffs say more about a move so they are rewarded by the square of the depth
+    pub fn reward(&mut self, colour: Colour, m: Move, depth: isize) {
+        let index = Self::index(colour, m);
+        self.scores[index] += (depth * depth) as i32;
+        if self.scores[index] > HISTORY_LIMIT {
+            self.scores.iter_mut().for_each(|score| *score /= 2);
+        }
+    }
+}
+
+pub struct MovePicker {
+    // The stage of the move last handed out
+    stage: Stage,
+    // Moves not yet given to a stage
+    remaining: Vec<Move>,
+    killers: [Option<Move>; 2],
+    // The scored moves of the current stage, best taken first
+    current: Vec<(Move, i32)>,
+    bad_captures: Vec<(Move, i32)>,
+}
+
+impl MovePicker {
+    pub fn new(mut moves: Vec<Move>, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        let mut current = Vec::default();
+        if let Some(index) = hash_move.and_then(|hash_move| moves.iter().position(|m| *m == hash_move)) {
+            current.push((moves.swap_remove(index), 0));
+        }
+        MovePicker{
+            stage: Stage::HashMove,
+            remaining: moves,
+            killers,
+            current,
+            bad_captures: Vec::default(),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self, board: &Board, history: &History) -> Option<Move> {
+        loop {
+            if let Some(index) = (0..self.current.len()).max_by_key(|index| self.current[*index].1) {
+                return Some(self.current.swap_remove(index).0);
+            }
+            self.stage = match self.stage {
+                Stage::HashMove => Stage::GoodCaptures,
+                Stage::GoodCaptures => Stage::Promotions,
+                Stage::Promotions => Stage::Killers,
+                Stage::Killers => Stage::Quiets,
+                Stage::Quiets => Stage::BadCaptures,
+                Stage::BadCaptures | Stage::Done => Stage::Done,
+            };
+            if self.stage == Stage::Done {
+                return None;
+            }
+            self.fill(board, history);
+        }
+    }
+
+    fn fill(&mut self, board: &Board, history: &History) {
+        match self.stage {
+            Stage::GoodCaptures => {
+                for (m, score) in self.take(|m| board.is_capture(*m), |m| mvv_lva(board, m)) {
+                    if is_good_capture(board, m) {
+                        self.current.push((m, score));
+                    } else {
+                        self.bad_captures.push((m, score));
+                    }
+                }
+            },
+            Stage::Promotions => self.current = self.take(|m| m.promote.is_some(), |m| m.promote.map_or(0, piece_value)),
+            Stage::Killers => {
+                let killers = self.killers;
+                self.current = self.take(|m| killers.contains(&Some(*m)), |m| if killers[0] == Some(m) { 1 } else { 0 });
+            },
+            Stage::Quiets => self.current = self.take(|_| true, |m| history.get(board.turn, m)),
+            Stage::BadCaptures => self.current = std::mem::take(&mut self.bad_captures),
+            Stage::HashMove | Stage::Done => (),
+        }
+    }
+
+    // Removes the remaining moves matching the filter and scores them
+    fn take<F: Fn(&Move) -> bool, S: Fn(Move) -> i32>(&mut self, filter: F, score: S) -> Vec<(Move, i32)> {
+        let (taken, rest): (Vec<Move>, Vec<Move>) = self.remaining.iter().partition(|m| filter(m));
+        self.remaining = rest;
+        taken.into_iter().map(|m| (m, score(m))).collect()
+    }
+}
+
+fn piece_value(kind: PieceKind) -> i32 {
+    MaterialEvaluator::get_piece_value(Piece::new(kind, Colour::White)) as i32
+}
+
+// The captured and capturing pieces, en passant takes a pawn
+fn capture_kinds(board: &Board, m: Move) -> (PieceKind, PieceKind) {
+    let victim = board.get(m.to).map_or(PieceKind::Pawn, |piece| piece.kind);
+    let attacker = board.get(m.from).map_or(PieceKind::Pawn, |piece| piece.kind);
+    (victim, attacker)
+}
+
+// Most valuable victim first, then least valuable attacker
+fn mvv_lva(board: &Board, m: Move) -> i32 {
+    let (victim, attacker) = capture_kinds(board, m);
+    10 * piece_value(victim) - piece_value(attacker)
+}
+
+// A cheap stand in for static exchange evaluation: a capture cannot lose material when it takes at least
+// as much as it risks or nothing can take back
+fn is_good_capture(board: &Board, m: Move) -> bool {
+    let (victim, attacker) = capture_kinds(board, m);
+    piece_value(victim) >= piece_value(attacker)
+        || m.to.get_index().is_some_and(|index| !board.is_square_attacked(index, board.turn.opposite()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+
+    fn symbol(symbol: &str) -> Move {
+        Move::from_symbol(symbol).unwrap()
+    }
+
+    #[test]
+    fn hands_out_moves_in_stages() {
+        let board = fen_parser::parse("k7/4P3/8/3q4/2P1r3/8/4Q3/K7 w - - 0 1").unwrap();
+        let mut history = History::new();
+        history.reward(Colour::White, symbol("e2e3"), 4);
+        let mut picker = MovePicker::new(board.possible_moves(), Some(symbol("a1b1")), [Some(symbol("a1a2")), None]);
+        let mut picked = Vec::default();
+        while let Some(m) = picker.next(&board, &history) {
+            picked.push((m.to_symbol(), picker.stage));
+        }
+        assert_eq!(picked.len(), board.possible_moves().len());
+        assert_eq!(picked[0], (String::from("a1b1"), Stage::HashMove));
+        assert_eq!(picked[1], (String::from("c4d5"), Stage::GoodCaptures));
+        assert_eq!(picked[2], (String::from("e7e8q"), Stage::Promotions));
+        assert_eq!(picked[6], (String::from("a1a2"), Stage::Killers));
+        assert_eq!(picked[7], (String::from("e2e3"), Stage::Quiets));
+        assert_eq!(picked.last().unwrap(), &(String::from("e2e4"), Stage::BadCaptures));
+        assert_eq!(picker.stage, Stage::Done);
+    }
+
+    #[test]
+    fn orders_captures_by_victim_then_attacker() {
+        let board = fen_parser::parse("k7/8/8/2r1q3/3P4/5N2/8/7K w - - 0 1").unwrap();
+        let history = History::new();
+        let mut picker = MovePicker::new(board.possible_moves(), None, [None; 2]);
+        let captures: Vec<String> = (0..3).filter_map(|_| picker.next(&board, &history)).map(|m| m.to_symbol()).collect();
+        assert_eq!(captures, vec!["d4e5", "f3e5", "d4c5"]);
+        assert_eq!(picker.stage, Stage::GoodCaptures);
+    }
+}
@@ -9,8 +9,7 @@ const PUZZLES_PATH: &str = "puzzles.txt";
 fn get_puzzles_buf() -> BufReader<File> {
     
     let file = File::open(PUZZLES_PATH).unwrap();
-    let reader = BufReader::new(file);
-    reader
+    BufReader::new(file)
 }
 
 pub fn get_puzzle(puzzle_line: usize) -> Option<(String, String)> {
@@ -19,7 +18,7 @@ pub fn get_puzzle(puzzle_line: usize) -> Option<(String, String)> {
     parse_puzzle(line.unwrap())
 }
 
-pub fn next_move(fen: &String, moves: &String, config: EngineConfig) -> bool {
+pub fn next_move(fen: &str, moves: &str, config: EngineConfig) -> bool {
     
     let best_move = Engine::run_fen_engine(String::from(fen), config);
     println!("Engine move is {}", best_move.unwrap());
@@ -49,14 +48,11 @@ mod tests {
         let reader = get_puzzles_buf();
         for (i, line) in reader.lines().enumerate() {
             let line: String = line.unwrap();
-            match parse_puzzle(line) {
-                Some((fen, moves)) => {
-                    println!("\nPuzzle Line {}: {} = {}", i + 1, fen, moves);
-                    let result = next_move(&fen, &moves, config);
-                    assert!(result);
-                }
-                None => (),
-            };
+            if let Some((fen, moves)) = parse_puzzle(line) {
+                println!("\nPuzzle Line {}: {} = {}", i + 1, fen, moves);
+                let result = next_move(&fen, &moves, config);
+                assert!(result);
+            }
         }
     }
 }
\ No newline at end of file
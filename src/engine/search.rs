@@ -0,0 +1,572 @@
+use std::time::{Duration, Instant};
+use crate::chess::bitboard::EMPTY;
+use crate::chess::board::Board;
+use crate::chess::colour::Colour;
+use crate::chess::game_status::GameStatus;
+use crate::chess::piece_kind::PieceKind;
+use crate::chess::r#move::Move;
+use crate::chess::variant::Variant;
+use super::engine_config::EngineConfig;
+use super::evaluator::MaterialEvaluator;
+use super::move_picker::{History, MovePicker};
+use super::transposition::{Bound, TranspositionTable};
+
+// Score of a mate on the board, mates further away score less so the shortest one is preferred
+pub const MATE: f64 = 10000f64;
+pub const MAX_PLY: usize = 128;
+const INFINITY: f64 = MATE + 1f64;
+// The clock is only read every so many nodes as reading it is slow
+const CLOCK_CHECK_NODES: u128 = 1024;
+// Iterations are not started once this fraction of the time has passed as they would rarely finish
+const SOFT_TIME_FRACTION: f32 = 0.5;
+// Pawns a capture may gain beyond the captured piece, captures that cannot lift the score this close to alpha are skipped
+const DELTA_MARGIN: f64 = 2f64;
+// Scores this close apart count as equal, evaluations move in steps of at least half a pawn
+const NULL_WINDOW: f64 = 0.01;
+// Null move pruning needs this much depth and searches the reply this much shallower, more at greater depths
+const NULL_MOVE_DEPTH: isize = 3;
+const NULL_MOVE_REDUCTION: isize = 2;
+// Moves tried before late move reductions start, and the least depth they apply at
+const LMR_MOVES: usize = 3;
+const LMR_DEPTH: isize = 3;
+// Reverse futility pruning trusts the evaluation to stay above beta by this many pawns per ply up to this depth
+const RFP_MARGIN: f64 = 1.5;
+const RFP_DEPTH: isize = 3;
+// A quiet move one ply from the horizon is unlikely to gain more than this
+const FUTILITY_MARGIN: f64 = 2f64;
+
+// Counts for judging move ordering, a well ordered search mostly cuts off on the first move it tries
+#[derive(Clone, Copy, Default)]
+pub struct SearchStats {
+    pub nodes: u128,
+    pub cutoffs: u128,
+    pub first_move_cutoffs: u128,
+}
+
+impl SearchStats {
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        if self.cutoffs == 0 { 0f64 } else { self.first_move_cutoffs as f64 / self.cutoffs as f64 }
+    }
+}
+
+// A finished iteration of the search, the score is in pawns for the side to move at the root
+#[derive(Clone)]
+pub struct Iteration {
+    pub depth: usize,
+    pub score: f64,
+    pub pv: Vec<Move>,
+    pub stats: SearchStats,
+}
+
+impl Iteration {
+    // Moves until mate, positive when the side to move at the root mates
+    pub fn mate_in(&self) -> Option<isize> {
+        if self.score.abs() < MATE - MAX_PLY as f64 {
+            return None;
+        }
+        let plies = (MATE - self.score.abs()).round() as isize;
+        Some(self.score.signum() as isize * (plies + 1) / 2)
+    }
+}
+
+pub struct Search<'a> {
+    board: Board,
+    // Hashes of the positions before the current one, for spotting repetitions
+    history: Vec<u64>,
+    config: EngineConfig,
+    table: &'a mut TranspositionTable,
+    // Two quiet moves per ply that last caused a cutoff there, likely to do so again in sibling positions
+    killers: Vec<[Option<Move>; 2]>,
+    history_table: History,
+    // Plies to reduce late moves by, indexed by depth then by how many moves came before
+    reductions: Vec<Vec<isize>>,
+    // Cleared for the position right after a null move so two are never made in a row
+    null_allowed: bool,
+    deadline: Instant,
+    soft_deadline: Instant,
+    stats: SearchStats,
+    // The deadline is only kept once an iteration has finished so there is always a move to play
+    interruptible: bool,
+    stopped: bool,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(board: Board, history: Vec<u64>, config: EngineConfig, table: &'a mut TranspositionTable) -> Self {
+        let now = Instant::now();
+        table.new_search();
+        Search{
+            board,
+            history,
+            config,
+            table,
+            killers: vec![[None; 2]; MAX_PLY],
+            history_table: History::new(),
+            reductions: reductions(),
+            null_allowed: true,
+            deadline: now + Duration::from_secs_f32(config.time),
+            soft_deadline: now + Duration::from_secs_f32(config.time * SOFT_TIME_FRACTION),
+            stats: SearchStats::default(),
+            interruptible: false,
+            stopped: false,
+        }
+    }
+
+    // Searches one ply deeper each iteration until the time runs out or the depth limit is reached, returning the
+    // last finished iteration. The first iteration always finishes so there is a move to play
+    pub fn iterate<F: FnMut(&Iteration)>(&mut self, mut on_iteration: F) -> Option<Iteration> {
+        let mut best: Option<Iteration> = None;
+        let timed = self.config.depth.is_none();
+        for depth in 1..=self.config.depth.unwrap_or(MAX_PLY - 1).min(MAX_PLY - 1) {
+            let mut pv = Vec::default();
+            let score = self.root(depth as isize, &mut pv);
+            if self.stopped || pv.is_empty() {
+                break;
+            }
+            let iteration = Iteration{depth, score, pv, stats: self.stats};
+            on_iteration(&iteration);
+            let proven = iteration.mate_in().is_some() && MATE - score.abs() <= depth as f64;
+            best = Some(iteration);
+            self.interruptible = timed;
+            if proven || (timed && Instant::now() >= self.soft_deadline) {
+                break;
+            }
+        }
+        best
+    }
+
+    // The root searches the best move of the last iteration first, or of the last search when the position was
+    // expected, and stores its result so the next iteration can do the same
+    fn root(&mut self, depth: isize, pv: &mut Vec<Move>) -> f64 {
+        let previous = self.table.probe(self.board.hash(), 0).and_then(|entry| entry.best_move);
+        let mut picker = MovePicker::new(self.board.possible_moves(), previous, self.killers[0]);
+        let mut alpha = -INFINITY;
+        while let Some(m) = picker.next(&self.board, &self.history_table) {
+            let mut child_pv = Vec::default();
+            let score = -self.child(m, depth, 1, -INFINITY, -alpha, 0, &mut child_pv);
+            if self.stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(m);
+                pv.extend(child_pv);
+            }
+        }
+        if !self.stopped {
+            self.table.store(self.board.hash(), depth, Bound::Exact, alpha, pv.first().copied(), 0);
+        }
+        alpha
+    }
+
+    // Plays a move and searches the position after it. A reduced move is first only tested against the parent's
+    // alpha with a null window, and searched again to full depth with the full window if it may beat it. Moves
+    // giving check are never reduced
+    #[allow(clippy::too_many_arguments)]
+    fn child(&mut self, m: Move, depth: isize, ply: usize, alpha: f64, beta: f64, reduction: isize, pv: &mut Vec<Move>) -> f64 {
+        self.history.push(self.board.hash());
+        let undo = self.board.make_move(m);
+        let reduced = reduction > 0 && !self.board.is_check(self.board.turn);
+        let mut score = -INFINITY;
+        if reduced {
+            score = self.alpha_beta(depth - 1 - reduction, ply, beta - NULL_WINDOW, beta, pv);
+        }
+        if !reduced || score < beta {
+            score = self.alpha_beta(depth - 1, ply, alpha, beta, pv);
+        }
+        self.board.unmake_move(undo);
+        self.history.pop();
+        score
+    }
+
+    fn alpha_beta(&mut self, depth: isize, ply: usize, mut alpha: f64, beta: f64, pv: &mut Vec<Move>) -> f64 {
+        let null_allowed = std::mem::replace(&mut self.null_allowed, true);
+        if depth <= 0 || ply >= MAX_PLY {
+            return self.quiescence(ply, 0, alpha, beta, pv);
+        }
+        if let Some(score) = self.visit(ply) {
+            return score;
+        }
+        if self.board.draw_by_rule(&self.history).is_some() {
+            return 0f64;
+        }
+
+        let hash = self.board.hash();
+        let entry = self.table.probe(hash, ply);
+        if let Some(entry) = entry.filter(|entry| entry.depth as isize >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                if entry.bound == Bound::Exact {
+                    *pv = self.table_line(depth);
+                }
+                return entry.score;
+            }
+        }
+
+        let moves = self.board.possible_moves();
+        let in_check = self.board.is_check(self.board.turn);
+        if moves.is_empty() {
+            return if in_check { -(MATE - ply as f64) } else { 0f64 };
+        }
+        let static_eval = MaterialEvaluator::evaluate(&self.board, self.board.turn);
+        let mate_window = alpha.abs() >= MATE - MAX_PLY as f64 || beta.abs() >= MATE - MAX_PLY as f64;
+
+        // The position is so far above beta that no reply is expected to bring it back
+        if self.config.reverse_futility_pruning && !in_check && !mate_window && depth <= RFP_DEPTH
+            && static_eval - RFP_MARGIN * depth as f64 >= beta {
+            return static_eval;
+        }
+
+        // Passing the turn is almost always worse than the best move, so if passing still holds beta the best
+        // move will too. Zugzwang breaks this, which is common once only pawns are left and everywhere in antichess
+        if self.config.null_move_pruning && null_allowed && !in_check && !mate_window && depth >= NULL_MOVE_DEPTH
+            && static_eval >= beta && self.has_pieces(self.board.turn) && self.board.variant != Variant::Antichess {
+            let saved = self.board;
+            self.board.make_null_move();
+            // The pass is left out of the history and restarts the count of reversible moves, so no repetition is
+            // ever matched across it
+            self.board.half_move_number = 0;
+            self.null_allowed = false;
+            let reduction = NULL_MOVE_REDUCTION + depth / 6;
+            let score = -self.alpha_beta(depth - 1 - reduction, ply + 1, -beta, -beta + NULL_WINDOW, &mut Vec::default());
+            self.board = saved;
+            if self.stopped {
+                return 0f64;
+            }
+            if score >= beta {
+                return beta;
+            }
+        }
+
+        // One ply from the horizon a quiet move cannot lift a position this far below alpha
+        let futile = self.config.futility_pruning && !in_check && !mate_window && depth == 1
+            && static_eval + FUTILITY_MARGIN <= alpha;
+
+        let hash_move = entry.and_then(|entry| entry.best_move);
+        let mut picker = MovePicker::new(moves, hash_move, self.killers[ply]);
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut searched = 0;
+        while let Some(m) = picker.next(&self.board, &self.history_table) {
+            let quiet = !self.board.is_capture(m) && m.promote.is_none();
+            if futile && quiet && searched > 0 && !self.gives_check(m) {
+                continue;
+            }
+            let reduction = if self.config.late_move_reductions && quiet && !in_check && depth >= LMR_DEPTH && searched >= LMR_MOVES {
+                self.reductions[depth as usize][searched.min(63)]
+            } else {
+                0
+            };
+            let mut child_pv = Vec::default();
+            let score = -self.child(m, depth, ply + 1, -beta, -alpha, reduction, &mut child_pv);
+            if self.stopped {
+                return 0f64;
+            }
+            searched += 1;
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(m);
+                pv.clear();
+                pv.push(m);
+                pv.extend(child_pv);
+                if alpha >= beta {
+                    self.stats.cutoffs += 1;
+                    if searched == 1 {
+                        self.stats.first_move_cutoffs += 1;
+                    }
+                    if quiet {
+                        self.add_killer(ply, m);
+                        self.history_table.reward(self.board.turn, m, depth);
+                    }
+                    break;
+                }
+            }
+        }
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table.store(hash, depth, bound, best, best_move, ply);
+        best
+    }
+
+    // Counts the node and watches the clock, gives the score when the search stopped or a variant rule ended the game
+    fn visit(&mut self, ply: usize) -> Option<f64> {
+        self.stats.nodes += 1;
+        if self.interruptible && self.stats.nodes.is_multiple_of(CLOCK_CHECK_NODES) && Instant::now() >= self.deadline {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return Some(0f64);
+        }
+        match self.board.variant_status() {
+            Some(GameStatus::VariantLoss) => Some(-(MATE - ply as f64)),
+            Some(GameStatus::VariantWin) => Some(MATE - ply as f64),
+            Some(GameStatus::VariantDraw) => Some(0f64),
+            _ => None,
+        }
+    }
+
+    // Searches captures and promotions until the position is quiet so the evaluation is never taken in the middle
+    // of an exchange. The side to move may stand pat on the evaluation unless it is in check or has to capture in
+    // antichess, when every move is tried
+    fn quiescence(&mut self, ply: usize, q_ply: usize, mut alpha: f64, beta: f64, pv: &mut Vec<Move>) -> f64 {
+        if let Some(score) = self.visit(ply) {
+            return score;
+        }
+        let in_check = self.board.is_check(self.board.turn);
+        let moves = self.board.possible_moves();
+        if moves.is_empty() {
+            return if in_check { -(MATE - ply as f64) } else { 0f64 };
+        }
+        let stand_pat = MaterialEvaluator::evaluate(&self.board, self.board.turn);
+        if ply >= MAX_PLY {
+            return stand_pat;
+        }
+        let forced = in_check || (self.board.variant == Variant::Antichess && moves.iter().any(|m| self.board.is_capture(*m)));
+        let mut best = -INFINITY;
+        if !forced {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            best = stand_pat;
+            alpha = alpha.max(stand_pat);
+        }
+        let checks = self.config.quiescence_checks && q_ply == 0;
+        let delta_pruning = !forced && self.captures_gain_their_value();
+        let mut searched = Vec::default();
+        for m in moves {
+            if forced || self.board.is_capture(m) || m.promote.is_some() || (checks && self.gives_check(m)) {
+                searched.push(m);
+            }
+        }
+        let mut picker = MovePicker::new(searched, None, [None; 2]);
+        while let Some(m) = picker.next(&self.board, &self.history_table) {
+            if delta_pruning && m.promote.is_none() && stand_pat + self.captured_value(m) + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            self.history.push(self.board.hash());
+            let undo = self.board.make_move(m);
+            let mut child_pv = Vec::default();
+            let score = -self.quiescence(ply + 1, q_ply + 1, -beta, -alpha, &mut child_pv);
+            self.board.unmake_move(undo);
+            self.history.pop();
+            if self.stopped {
+                return 0f64;
+            }
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(m);
+                pv.extend(child_pv);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // Whether the side has anything besides its king and pawns
+    fn has_pieces(&self, colour: Colour) -> bool {
+        let pieces = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+        pieces.iter().any(|kind| self.board.pieces_of(*kind, colour) != EMPTY)
+    }
+
+    fn add_killer(&mut self, ply: usize, m: Move) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+    }
+
+    fn gives_check(&mut self, m: Move) -> bool {
+        let undo = self.board.make_move(m);
+        let check = self.board.is_check(self.board.turn);
+        self.board.unmake_move(undo);
+        check
+    }
+
+    // Delta pruning needs a capture to gain exactly the captured piece, which atomic explosions, crazyhouse pockets
+    // and antichess scoring all break
+    fn captures_gain_their_value(&self) -> bool {
+        !matches!(self.board.variant, Variant::Crazyhouse | Variant::Atomic | Variant::Antichess)
+    }
+
+    // The en passant square is empty so a capture there takes a pawn
+    fn captured_value(&self, m: Move) -> f64 {
+        self.board.get(m.to).map_or(1, MaterialEvaluator::get_piece_value) as f64
+    }
+
+    // The line of best moves stored in the table from this position, for when an exact entry cuts the search short
+    fn table_line(&mut self, length: isize) -> Vec<Move> {
+        let mut line = Vec::default();
+        let mut undos = Vec::default();
+        while (line.len() as isize) < length {
+            let next = self.table.probe(self.board.hash(), 0)
+                .and_then(|entry| entry.best_move)
+                .filter(|m| self.board.possible_moves().contains(m));
+            match next {
+                Some(m) => {
+                    undos.push(self.board.make_move(m));
+                    line.push(m);
+                },
+                None => break,
+            }
+        }
+        while let Some(undo) = undos.pop() {
+            self.board.unmake_move(undo);
+        }
+        line
+    }
+}
+
+// Late moves are reduced more the deeper the search and the later the move, growing with the log of both
+fn reductions() -> Vec<Vec<isize>> {
+    (0..MAX_PLY).map(|depth| (0..64).map(|moves| {
+        if depth == 0 || moves == 0 {
+            0
+        } else {
+            (0.75 + (depth as f64).ln() * (moves as f64).ln() / 2.25) as isize
+        }
+    }).collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::fen_parser;
+
+    fn search(fen: &str, time: f32) -> Iteration {
+        let board = fen_parser::parse(fen).unwrap();
+        let mut config = EngineConfig::default();
+        config.time = time;
+        Search::new(board, Vec::default(), config, &mut TranspositionTable::new(1)).iterate(|_| ()).unwrap()
+    }
+
+    #[test]
+    fn finds_mate_with_full_line() {
+        let iteration = search("1b2K3/8/8/5b2/8/6RQ/5k2/1q6 w - - 0 1", 10f32);
+        assert_eq!(iteration.mate_in(), Some(2));
+        assert_eq!(iteration.pv.len(), 3);
+        assert!(["h3h2", "g3g2"].contains(&iteration.pv[0].to_symbol().as_str()));
+    }
+
+    #[test]
+    fn first_depth_finishes_without_time() {
+        let board = fen_parser::parse_variant("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", Variant::Crazyhouse).unwrap();
+        let mut config = EngineConfig::default();
+        config.time = 0f32;
+        let iteration = Search::new(board, Vec::default(), config, &mut TranspositionTable::new(1)).iterate(|_| ()).unwrap();
+        assert_eq!(iteration.depth, 1);
+        assert!(iteration.stats.nodes > CLOCK_CHECK_NODES);
+        assert!(!iteration.pv.is_empty());
+    }
+
+    #[test]
+    fn antichess_cannot_stand_pat_before_a_capture() {
+        // White must take the pawn and is then left as the only side with pieces
+        let board = fen_parser::parse_variant("8/8/8/8/8/8/p7/R7 w - - 0 1", Variant::Antichess).unwrap();
+        let mut table = TranspositionTable::new(1);
+        let mut search = Search::new(board, Vec::default(), EngineConfig::default(), &mut table);
+        let mut pv = Vec::default();
+        assert_eq!(search.quiescence(0, 0, -INFINITY, INFINITY, &mut pv), -(MATE - 1f64));
+        assert_eq!(pv, vec![Move::from_symbol("a1a2").unwrap()]);
+    }
+
+    #[test]
+    fn reports_every_finished_depth() {
+        let board = fen_parser::parse("startpos").unwrap();
+        let mut config = EngineConfig::default();
+        config.time = 0f32;
+        config.depth = Some(4);
+        let mut depths = Vec::default();
+        let mut table = TranspositionTable::new(1);
+        let last = Search::new(board, Vec::default(), config, &mut table).iterate(|iteration| depths.push(iteration.depth)).unwrap();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(last.depth, 4);
+        assert!(!last.pv.is_empty());
+    }
+
+    #[test]
+    fn reuses_the_table_between_searches() {
+        let board = fen_parser::parse("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut config = EngineConfig::default();
+        config.depth = Some(4);
+        let mut table = TranspositionTable::new(16);
+        let nodes = |table: &mut TranspositionTable| Search::new(board, Vec::default(), config, table).iterate(|_| ()).unwrap().stats.nodes;
+        let first = nodes(&mut table);
+        let second = nodes(&mut table);
+        assert!(second < first, "{} nodes again against {} at first", second, first);
+    }
+
+    #[test]
+    fn sees_recaptures_past_the_horizon() {
+        let board = fen_parser::parse("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut table = TranspositionTable::new(1);
+        let mut search = Search::new(board, Vec::default(), EngineConfig::default(), &mut table);
+        let mut pv = Vec::default();
+        let score = search.root(1, &mut pv);
+        assert_ne!(pv[0].to_symbol(), "d1d5");
+        assert_eq!(score, 7f64);
+    }
+
+    #[test]
+    fn pruning_searches_fewer_nodes() {
+        let board = fen_parser::parse("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let nodes_to_depth = |config: EngineConfig| {
+            let mut table = TranspositionTable::new(16);
+            Search::new(board, Vec::default(), config, &mut table).iterate(|_| ()).unwrap().stats.nodes
+        };
+        let mut pruned = EngineConfig::default();
+        pruned.depth = Some(4);
+        let mut full = pruned;
+        full.null_move_pruning = false;
+        full.late_move_reductions = false;
+        full.reverse_futility_pruning = false;
+        full.futility_pruning = false;
+        let full_nodes = nodes_to_depth(full);
+        let pruned_nodes = nodes_to_depth(pruned);
+        assert!(pruned_nodes < full_nodes, "{} pruned against {} full", pruned_nodes, full_nodes);
+    }
+
+    #[test]
+    fn null_move_needs_pieces() {
+        let board = fen_parser::parse("4k3/pp6/8/8/8/8/PP6/4KN2 w - - 0 1").unwrap();
+        let mut table = TranspositionTable::new(1);
+        let search = Search::new(board, Vec::default(), EngineConfig::default(), &mut table);
+        assert!(search.has_pieces(Colour::White));
+        assert!(!search.has_pieces(Colour::Black));
+    }
+
+    #[test]
+    fn reduces_later_moves_more() {
+        let table = reductions();
+        assert_eq!(table[1][10], 0);
+        assert!(table[3][3] >= 1);
+        assert!(table[20][40] > table[20][4]);
+        assert!(table[20][40] > table[4][40]);
+    }
+
+    #[test]
+    fn takes_hanging_queen() {
+        let iteration = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 1f32);
+        assert_eq!(iteration.pv[0].to_symbol(), "d2d5");
+        assert!(iteration.score > 4f64);
+    }
+}
@@ -0,0 +1,11 @@
+use crate::chess::r#move::Move;
+
+// What the engine found when choosing a move, sent back to whoever asked for the move
+#[derive(Clone, Copy)]
+pub struct SearchInfo {
+    pub ply: usize,
+    pub best_move: Move,
+    pub evaluation: f64,
+    pub mate_in: Option<isize>,
+    pub depth: usize,
+}
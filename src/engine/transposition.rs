@@ -0,0 +1,172 @@
+use std::mem;
+use crate::chess::r#move::Move;
+use super::search::{MATE, MAX_PLY};
+
+const BUCKET_SIZE: usize = 4;
+// Searches an entry may fall behind before a shallower entry of the current search can replace it
+const AGE_WEIGHT: i16 = 8;
+
+// How a stored score relates to the true score of the position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    // The search failed high, the true score is at least this
+    Lower,
+    // The search failed low, the true score is at most this
+    Upper,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub best_move: Option<Move>,
+    pub score: f64,
+    pub depth: i16,
+    pub bound: Bound,
+    pub age: u8,
+}
+
+type Bucket = [Option<Entry>; BUCKET_SIZE];
+
+// Positions already searched, kept between searches and shared by every game in play. Each key maps to a bucket of a few entries
+// and a full bucket gives up its least useful entry, counting depth and how many searches ago it was written
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        let count = (megabytes * 1024 * 1024 / mem::size_of::<Bucket>()).max(1);
+        TranspositionTable{buckets: vec![[None; BUCKET_SIZE]; count], age: 0}
+    }
+
+    // Called before each search so entries from earlier moves are replaced first
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn bucket(&self, key: u64) -> usize {
+        ((key as u128 * self.buckets.len() as u128) >> 64) as usize
+    }
+
+    // The stored entry with its mate score made relative to this ply again
+    pub fn probe(&self, key: u64, ply: usize) -> Option<Entry> {
+        let entry = self.buckets[self.bucket(key)].iter().flatten().find(|entry| entry.key == key)?;
+        Some(Entry{score: from_table(entry.score, ply), ..*entry})
+    }
+
+    pub fn store(&mut self, key: u64, depth: isize, bound: Bound, score: f64, best_move: Option<Move>, ply: usize) {
+        let age = self.age;
+        let index = self.bucket(key);
+        let bucket = &mut self.buckets[index];
+        let slot = match bucket.iter().position(|entry| entry.is_some_and(|entry| entry.key == key)) {
+            Some(slot) => {
+                let old = bucket[slot].unwrap();
+                // A deeper result from this search is kept over a shallower bound
+                if old.age == age && old.depth > depth as i16 && bound != Bound::Exact {
+                    return;
+                }
+                slot
+            },
+            None => (0..BUCKET_SIZE).min_by_key(|slot| match bucket[*slot] {
+                None => i16::MIN,
+                Some(entry) => entry.depth - AGE_WEIGHT * age.wrapping_sub(entry.age) as i16,
+            }).unwrap(),
+        };
+        let best_move = best_move.or_else(|| bucket[slot].filter(|entry| entry.key == key).and_then(|entry| entry.best_move));
+        bucket[slot] = Some(Entry{key, best_move, score: to_table(score, ply), depth: depth as i16, bound, age});
+    }
+
+    // Permille of the first thousand buckets' entries written in this search, as UCI reports hash use
+    pub fn hashfull(&self) -> usize {
+        let sample = &self.buckets[..self.buckets.len().min(1000)];
+        let used = sample.iter().flatten().flatten().filter(|entry| entry.age == self.age).count();
+        used * 1000 / (sample.len() * BUCKET_SIZE)
+    }
+}
+
+// Mate scores count plies from the root, the table stores them counted from the position itself
+fn to_table(score: f64, ply: usize) -> f64 {
+    if score >= MATE - MAX_PLY as f64 {
+        score + ply as f64
+    } else if score <= -(MATE - MAX_PLY as f64) {
+        score - ply as f64
+    } else {
+        score
+    }
+}
+
+fn from_table(score: f64, ply: usize) -> f64 {
+    if score >= MATE - MAX_PLY as f64 {
+        score - ply as f64
+    } else if score <= -(MATE - MAX_PLY as f64) {
+        score + ply as f64
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn e2e4() -> Option<Move> {
+        Move::from_symbol("e2e4")
+    }
+
+    #[test]
+    fn stores_and_probes() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.probe(42, 0), None);
+        table.store(42, 5, Bound::Lower, 1.5, e2e4(), 3);
+        let entry = table.probe(42, 0).unwrap();
+        assert_eq!((entry.depth, entry.bound, entry.score, entry.best_move), (5, Bound::Lower, 1.5, e2e4()));
+    }
+
+    #[test]
+    fn sizes_from_megabytes() {
+        let table = TranspositionTable::new(2);
+        assert_eq!(table.buckets.len(), 2 * 1024 * 1024 / mem::size_of::<Bucket>());
+        assert_eq!(TranspositionTable::new(0).buckets.len(), 1);
+    }
+
+    #[test]
+    fn keeps_mates_relative_to_the_position() {
+        let mut table = TranspositionTable::new(1);
+        // Mate two plies after a position reached at ply 3
+        table.store(7, 4, Bound::Exact, MATE - 5f64, None, 3);
+        assert_eq!(table.probe(7, 1).unwrap().score, MATE - 3f64);
+        table.store(8, 4, Bound::Exact, -(MATE - 5f64), None, 3);
+        assert_eq!(table.probe(8, 5).unwrap().score, -(MATE - 7f64));
+    }
+
+    #[test]
+    fn replaces_shallow_and_old_entries() {
+        let mut table = TranspositionTable::new(0);
+        for key in 0..BUCKET_SIZE as u64 {
+            table.store(key, 10 - key as isize, Bound::Exact, 0f64, None, 0);
+        }
+        // The shallowest entry makes way
+        table.store(100, 1, Bound::Exact, 0f64, None, 0);
+        assert_eq!(table.probe(3, 0), None);
+        assert!(table.probe(100, 0).is_some());
+
+        // A shallower bound for a known position keeps the deeper result and its move
+        table.store(0, 10, Bound::Exact, 0f64, e2e4(), 0);
+        table.store(0, 2, Bound::Upper, -1f64, None, 0);
+        assert_eq!(table.probe(0, 0).unwrap().depth, 10);
+
+        // Entries from earlier searches go before deep ones from this search
+        table.new_search();
+        table.new_search();
+        table.store(0, 1, Bound::Upper, -1f64, None, 0);
+        assert_eq!(table.probe(0, 0).unwrap().best_move, e2e4());
+        for key in 200..203 {
+            table.store(key, 1, Bound::Exact, 0f64, None, 0);
+        }
+        assert!(table.probe(0, 0).is_some());
+        assert_eq!(table.probe(1, 0), None);
+        assert_eq!(table.hashfull(), 1000);
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Serialize, Deserialize};
 use super::*;
 use crate::engine::game::Game;
+use crate::chess::board::Board;
 use crate::chess::fen_parser;
+use crate::chess::variant::Variant;
 use crate::chess::colour::Colour;
 use super::super::API;
 
@@ -25,12 +27,19 @@ impl GameFull {
     pub fn to_engine_game(&self, api: &API) -> Game {
         Game {
             game_id: self.id.clone(),
-            initial_board: fen_parser::parse(&self.initial_fen).unwrap(),
+            initial_board: self.initial_board(),
             moves: self.state.moves.clone(),
             my_side: self.get_my_side(api),
+            search_info: None,
         }
     }
 
+    // The starting position played under the game's variant, unknown variants fall back to standard rules
+    pub fn initial_board(&self) -> Board {
+        let variant = Variant::from_key(&self.variant.key).unwrap_or(Variant::Standard);
+        fen_parser::parse_variant(&self.initial_fen, variant).unwrap()
+    }
+
     fn get_my_side(&self, api: &API) -> Colour {
         if self.white.id == api.config.lichess.my_id {
             Colour::White
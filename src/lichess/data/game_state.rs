@@ -3,7 +3,7 @@ use crate::engine::game::Game;
 use super::game_full::GameFull;
 use super::super::API;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameState {
     pub r#type: String,
     pub moves: String,
@@ -13,9 +13,27 @@ pub struct GameState {
     pub binc: u128,
     pub wdraw: bool,
     pub bdraw: bool,
+    pub status: Option<String>,
+    pub winner: Option<String>,
 }
 
 impl GameState {
+    pub fn is_finished(&self) -> bool {
+        match &self.status {
+            Some(status) => status != "created" && status != "started",
+            None => false,
+        }
+    }
+
+    pub fn result(&self) -> &str {
+        match (self.winner.as_deref(), self.status.as_deref()) {
+            (Some("white"), _) => "1-0",
+            (Some("black"), _) => "0-1",
+            (_, Some("created")) | (_, Some("started")) | (_, Some("aborted")) | (_, Some("noStart")) | (_, Some("unknownFinish")) | (_, None) => "*",
+            _ => "1/2-1/2",
+        }
+    }
+
     pub fn to_engine_game(&self, api: &API, full_game: &GameFull) -> Game {
         let mut game = full_game.to_engine_game(api);
         game.moves = self.moves.clone();
@@ -1,8 +1,8 @@
 extern crate serde;
 extern crate reqwest;
 
-use serde_json;
 use std::io::prelude::*;
+use std::io::BufReader;
 use std::convert::AsRef;
 use std::fmt::Write;
 use std::thread;
@@ -23,14 +23,15 @@ impl API {
         }
         let mut buffer = String::default();
         let mut threads: Vec<JoinHandle<()>> = Vec::default();
-        for b in response.bytes() {
+        for b in BufReader::new(response).bytes() {
             let c = match b {
                 Ok(c) => c as char,
                 Err(_) => continue,
             };
             if c == '\n' {
-                self.parse_event(buffer.clone(), tx.clone())
-                    .and_then(|thread| {Some(threads.push(thread))});
+                if let Some(thread) = self.parse_event(buffer.clone(), tx.clone()) {
+                    threads.push(thread);
+                }
                 buffer = String::default();
             } else {
                 buffer.write_char(c).expect("Write character to buffer");
@@ -44,7 +45,7 @@ impl API {
     }
 
     fn parse_event(&self, json: String, tx: Sender<Game>) -> Option<JoinHandle<()>> {
-        if json == "" {
+        if json.is_empty() {
             return None;
         }
         let event: data::event::Event = serde_json::from_str(&json).expect("Cannot deserialize event json");
@@ -1,28 +1,38 @@
 extern crate serde;
 extern crate reqwest;
 
-use serde_json;
 use std::io::prelude::*;
+use std::io::BufReader;
 use std::convert::AsRef;
 use std::fmt::Write;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{channel, Receiver, Sender};
 
 use super::data;
+use super::pgn_export;
+use crate::chess::colour::Colour;
 use crate::engine::game::Game;
+use crate::engine::search_info::SearchInfo;
 use data::game_full::GameFull;
+use data::game_state::GameState;
 use super::API;
 
 impl API {
     pub fn run_game_stream(&self, game_id: String, tx: Sender<Game>) {
         let url = format!("bot/game/stream/{}", game_id);
         let response: reqwest::Response = self.auth_get(url).unwrap();
+        let (search_tx, search_rx) = channel();
         let mut game_stream = GameStream {
             api: self.clone(),
             game_full: None,
-            tx: tx,
+            tx,
+            search_tx,
+            search_rx,
+            searches: Vec::default(),
+            clocks: Vec::default(),
+            pgn_written: false,
         };
         let mut buffer = String::default();
-        for b in response.bytes() {
+        for b in BufReader::new(response).bytes() {
             let c = match b {
                 Ok(b) => b as char,
                 Err(_) => break,
@@ -41,12 +51,18 @@ struct GameStream {
     api: API,
     game_full: Option<GameFull>,
     tx: Sender<Game>,
+    search_tx: Sender<SearchInfo>,
+    search_rx: Receiver<SearchInfo>,
+    searches: Vec<SearchInfo>,
+    // Time left for the side that made each move, by ply
+    clocks: Vec<Option<u128>>,
+    pgn_written: bool,
 }
 
 impl GameStream {
 
     fn parse_game(&mut self, json: String) {
-        if &json.len() <= &2 {
+        if json.len() <= 2 {
             return;
         }
         let game: data::game::Game = serde_json::from_str(&json).unwrap();
@@ -62,13 +78,51 @@ impl GameStream {
 
     fn parse_game_full(&mut self, json: String) {
         let game_full: GameFull = serde_json::from_str(&json).unwrap();
-        self.tx.send(game_full.to_engine_game(&self.api)).unwrap();
+        let mut game = game_full.to_engine_game(&self.api);
+        game.search_info = Some(self.search_tx.clone());
+        self.tx.send(game).unwrap();
+        let state = game_full.state.clone();
         self.game_full = Some(game_full);
+        self.update_state(&state);
     }
 
-    fn parse_game_state(&self, json: String) {
-        let game_state: data::game_state::GameState = serde_json::from_str(&json).unwrap();
-        self.tx.send(game_state.to_engine_game(&self.api, self.game_full.as_ref().unwrap())).expect("Parse game_state");
+    fn parse_game_state(&mut self, json: String) {
+        let game_state: GameState = serde_json::from_str(&json).unwrap();
+        let mut game = game_state.to_engine_game(&self.api, self.game_full.as_ref().unwrap());
+        game.search_info = Some(self.search_tx.clone());
+        self.tx.send(game).expect("Parse game_state");
+        self.update_state(&game_state);
+    }
+
+    fn update_state(&mut self, state: &GameState) {
+        let game_full = self.game_full.as_ref().unwrap();
+        let ply_count = state.moves.split(' ').filter(|symbol| !symbol.is_empty()).count();
+        if ply_count > 0 {
+            let initial_turn = game_full.initial_board().turn;
+            let time = match pgn_export::mover(initial_turn, ply_count - 1) {
+                Colour::White => state.wtime,
+                Colour::Black => state.btime,
+            };
+            self.clocks.resize(ply_count, None);
+            self.clocks[ply_count - 1] = Some(time);
+        }
+        if state.is_finished() && !self.pgn_written {
+            self.pgn_written = true;
+            self.write_pgn(state);
+        }
+    }
+
+    fn write_pgn(&mut self, state: &GameState) {
+        let directory = match &self.api.config.lichess.pgn_directory {
+            Some(directory) => directory.clone(),
+            None => return,
+        };
+        self.searches.extend(self.search_rx.try_iter());
+        let game_full = self.game_full.as_ref().unwrap();
+        let pgn = pgn_export::to_pgn_game(game_full, state, &self.searches, &self.clocks).to_pgn();
+        if let Err(e) = pgn_export::write(&directory, &game_full.id, &pgn) {
+            println!("Could not write PGN for {}: {}", game_full.id, e);
+        }
     }
 
     fn parse_chat_line(&self, json: String) {
@@ -3,13 +3,16 @@ extern crate reqwest;
 mod data;
 mod event_stream;
 mod game_stream;
+mod pgn_export;
 use crate::config;
 
 use std::sync::mpsc::Sender;
 use crate::engine::game::Game;
+use crate::chess::variant::Variant;
 
 use reqwest::{Client, Response, Error};
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub struct API {
     config: config::Config,
@@ -51,7 +54,8 @@ impl API {
     }
 
     pub fn decide_challenge(&self, challenge: data::challenge::Challenge) -> bool {
-        if self.config.lichess.challenge_filter.variant_whitelist.contains(&challenge.variant.key)
+        if Variant::from_key(&challenge.variant.key).is_some()
+            && self.config.lichess.challenge_filter.variant_whitelist.contains(&challenge.variant.key)
             && self.config.lichess.challenge_filter.time_control_whitelist.contains(&challenge.time_control.r#type) {
             self.accept_challenge(challenge.id)
         } else {
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::chess::colour::Colour;
+use crate::chess::pgn::PgnGame;
+use crate::chess::r#move::Move;
+use crate::engine::search_info::SearchInfo;
+use super::data::game_full::GameFull;
+use super::data::game_state::GameState;
+
+// Builds the record of a finished game, our moves carry the engine evaluation and every move carries the clock
+pub fn to_pgn_game(game_full: &GameFull, state: &GameState, searches: &[SearchInfo], clocks: &[Option<u128>]) -> PgnGame {
+    let initial_board = game_full.initial_board();
+    let mut board = initial_board;
+    let mut moves = Vec::default();
+    for symbol in state.moves.split(' ').filter(|symbol| !symbol.is_empty()) {
+        match Move::from_symbol(symbol) {
+            Some(m) if board.play_move(m) => moves.push(m),
+            _ => break,
+        }
+    }
+
+    let comments = moves.iter().enumerate().map(|(ply, m)| {
+        let mut comment = Vec::default();
+        if let Some(info) = searches.iter().rev().find(|info| info.ply == ply && info.best_move == *m) {
+            comment.push(eval_comment(info));
+        }
+        if let Some(Some(time)) = clocks.get(ply) {
+            comment.push(clock_comment(*time));
+        }
+        if comment.is_empty() { None } else { Some(comment.join(" ")) }
+    }).collect();
+
+    let result = String::from(state.result());
+    let mut tags = vec![
+        (String::from("Event"), format!("{} {} game", if game_full.rated { "Rated" } else { "Casual" }, capitalise(&game_full.speed))),
+        (String::from("Site"), format!("https://lichess.org/{}", game_full.id)),
+        (String::from("Date"), date(game_full.created_at)),
+        (String::from("Round"), String::from("-")),
+        (String::from("White"), game_full.white.name.clone()),
+        (String::from("Black"), game_full.black.name.clone()),
+        (String::from("Result"), result.clone()),
+        (String::from("WhiteElo"), game_full.white.rating.to_string()),
+        (String::from("BlackElo"), game_full.black.rating.to_string()),
+        (String::from("TimeControl"), match &game_full.clock {
+            Some(clock) => format!("{}+{}", clock.initial / 1000, clock.increment / 1000),
+            None => String::from("-"),
+        }),
+    ];
+    if game_full.variant.key != "standard" {
+        tags.push((String::from("Variant"), game_full.variant.name.clone()));
+    }
+    // Chess960 games always name their start, with rook files so the castling rights cannot be misread
+    if initial_board.chess960 {
+        tags.push((String::from("SetUp"), String::from("1")));
+        tags.push((String::from("FEN"), initial_board.to_shredder_fen()));
+    } else if game_full.initial_fen != "startpos" {
+        tags.push((String::from("SetUp"), String::from("1")));
+        tags.push((String::from("FEN"), game_full.initial_fen.clone()));
+    }
+
+    PgnGame{tags, initial_board, moves, comments, result: Some(result)}
+}
+
+pub fn write(directory: &str, game_id: &str, pgn: &str) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+    fs::write(Path::new(directory).join(format!("{}.pgn", game_id)), pgn)
+}
+
+// The side that made the move at this ply
+pub fn mover(initial_turn: Colour, ply: usize) -> Colour {
+    if ply.is_multiple_of(2) {
+        initial_turn
+    } else {
+        initial_turn.opposite()
+    }
+}
+
+// Lichess speeds are lower case but the Event tag names them as "Blitz" or "Correspondence"
+fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::default(),
+    }
+}
+
+// Evaluation in pawns from White's point of view followed by the search depth
+fn eval_comment(info: &SearchInfo) -> String {
+    match info.mate_in {
+        Some(mate_in) => format!("[%eval #{},{}]", mate_in, info.depth),
+        None => format!("[%eval {:.2},{}]", info.evaluation, info.depth),
+    }
+}
+
+fn clock_comment(time: u128) -> String {
+    let seconds = time / 1000;
+    format!("[%clk {}:{:02}:{:02}]", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+// Converts milliseconds since the Unix epoch to a PGN date, using the days to civil date algorithm from Howard Hinnant
+fn date(millis: u128) -> String {
+    let days = (millis / 86_400_000) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::pgn;
+    use crate::chess::variant::Variant;
+
+    fn game_full(key: &str, name: &str, fen: &str, moves: &str) -> GameFull {
+        let user = |id: &str| format!(r#"{{"id": "{}", "name": "{}", "rating": 1500}}"#, id, id);
+        serde_json::from_str(&format!(
+            r#"{{"type": "gameFull", "id": "abcdefgh", "rated": true, "variant": {{"key": "{}", "name": "{}", "short": "?"}},
+            "clock": null, "createdAt": 0, "white": {}, "black": {}, "initialFen": "{}", "speed": "blitz",
+            "state": {{"type": "gameState", "moves": "{}", "wtime": 0, "btime": 0, "winc": 0, "binc": 0,
+            "wdraw": false, "bdraw": false, "status": "resign", "winner": "white"}}}}"#,
+            key, name, user("one"), user("two"), fen, moves,
+        )).unwrap()
+    }
+
+    #[test]
+    fn variant_games_read_back() {
+        let games = [
+            ("chess960", "Chess960", "rk4r1/8/8/8/8/8/8/RK4R1 w KQkq - 0 1", "b1a1 b8a8 g1g8", Variant::Chess960),
+            ("crazyhouse", "Crazyhouse", "startpos", "e2e4 d7d5 e4d5 g8f6 P@e5", Variant::Crazyhouse),
+            ("threeCheck", "Three-check", "startpos", "e2e4 e7e5 f1c4 d7d6 c4f7", Variant::ThreeCheck),
+            ("kingOfTheHill", "King of the Hill", "startpos", "e2e4 e7e5 e1e2", Variant::KingOfTheHill),
+        ];
+        for (key, name, fen, moves, variant) in games.iter() {
+            let full = game_full(key, name, fen, moves);
+            let exported = to_pgn_game(&full, &full.state, &[], &[]);
+            assert_eq!(exported.moves.len(), moves.split(' ').count());
+            let read = pgn::parse(&exported.to_pgn()).next().unwrap().unwrap();
+            assert_eq!(read.initial_board.variant, *variant);
+            assert!(read.moves == exported.moves, "{}", name);
+            assert_eq!(read.tag("Event"), Some("Rated Blitz game"));
+        }
+        let full = game_full("chess960", "Chess960", "startpos", "");
+        assert_eq!(to_pgn_game(&full, &full.state, &[], &[]).tag("FEN"), Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"));
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(date(0), "1970.01.01");
+        assert_eq!(date(951_782_400_000), "2000.02.29");
+        assert_eq!(date(1_700_000_000_000), "2023.11.14");
+    }
+
+    #[test]
+    fn formats_comments() {
+        assert_eq!(clock_comment(5_025_900), "[%clk 1:23:45]");
+        assert_eq!(clock_comment(999), "[%clk 0:00:00]");
+        let mut info = SearchInfo{
+            ply: 0,
+            best_move: Move::from_symbol("e2e4").unwrap(),
+            evaluation: 0.35,
+            mate_in: None,
+            depth: 12,
+        };
+        assert_eq!(eval_comment(&info), "[%eval 0.35,12]");
+        info.mate_in = Some(-2);
+        assert_eq!(eval_comment(&info), "[%eval #-2,12]");
+    }
+}
@@ -6,8 +6,11 @@ mod config;
 use engine::puzzles::{get_puzzle, next_move};
 use engine::engine::Engine;
 use engine::engine_config::EngineConfig;
+use engine::book_builder::BookBuilder;
+use chess::colour::Colour;
 use std::env;
 use std::thread;
+use std::time::Instant;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -18,6 +21,16 @@ fn main() {
         return;
     }
 
+    if args.len() >= 3 && args[1] == "perft" {
+        run_perft(args);
+        return;
+    }
+
+    if args.len() >= 2 && args[1] == "build-book" {
+        run_build_book(args);
+        return;
+    }
+
     if args.len() > 2 {
         run_fen(args);
         return;
@@ -29,12 +42,54 @@ fn main() {
 fn run_puzzle(args: Vec<String>) {
     let config = EngineConfig::default_debug(true);
     let puzzle_line_number = args[2].parse::<usize>().unwrap();
-    match get_puzzle(puzzle_line_number) {
-        Some((fen, moves)) => {
-            next_move(&fen, &moves, config);
-        },
-        None => (),
-    };
+    if let Some((fen, moves)) = get_puzzle(puzzle_line_number) {
+        next_move(&fen, &moves, config);
+    }
+}
+
+// Prints the node count below each move and the total, the fen defaults to the start position
+fn run_perft(args: Vec<String>) {
+    let depth = args[2].parse::<usize>().expect("Perft depth must be a number");
+    let fen = if args.len() > 3 { args[3..].join(" ") } else { String::from("startpos") };
+    let board = chess::fen_parser::parse(&fen).expect("Invalid fen");
+    let now = Instant::now();
+    let divide = board.divide(depth);
+    for (m, nodes) in divide.iter() {
+        println!("{}: {}", m, nodes);
+    }
+    println!("Nodes searched: {}", divide.iter().map(|(_, nodes)| nodes).sum::<u64>());
+    println!("Elapsed: {}", now.elapsed().as_secs_f32());
+}
+
+// Builds a Polyglot book from the .pgn files in a directory,
+// options are --plies N, --min-games N and --colour white|black to only keep one side's moves
+fn run_build_book(args: Vec<String>) {
+    if args.len() < 4 {
+        println!("Usage: build-book <pgn directory> <book file> [--plies N] [--min-games N] [--colour white|black]");
+        return;
+    }
+    let mut builder = BookBuilder::new(20, 1, None);
+    let mut options = args[4..].iter();
+    while let Some(option) = options.next() {
+        let value = options.next().unwrap_or_else(|| panic!("Missing value for {}", option));
+        match option.as_str() {
+            "--plies" => builder.max_plies = value.parse().expect("Plies must be a number"),
+            "--min-games" => builder.min_games = value.parse().expect("Minimum games must be a number"),
+            "--colour" => builder.colour = match value.as_str() {
+                "white" => Some(Colour::White),
+                "black" => Some(Colour::Black),
+                _ => panic!("Colour must be white or black"),
+            },
+            _ => panic!("Unknown option {}", option),
+        }
+    }
+    let now = Instant::now();
+    let (games, errors) = builder.add_directory(&args[2]).expect("Could not read PGN directory");
+    let entries = builder.write(&args[3]).expect("Could not write book");
+    println!("Games used: {}", games);
+    println!("Games skipped with errors: {}", errors);
+    println!("Book entries: {}", entries);
+    println!("Elapsed: {}", now.elapsed().as_secs_f32());
 }
 
 fn run_fen(args: Vec<String>) {